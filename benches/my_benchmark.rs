@@ -1,5 +1,6 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
+// items of these modules which the bench doesn't call are allowed one by one in their files
 #[path = "../src/board.rs"]
 mod board;
 
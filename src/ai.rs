@@ -0,0 +1,258 @@
+//! computer player which thinks in its own thread, so the window doesn't freeze
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, Move};
+use crate::game::Game;
+use crate::player_minmax::{self, Analysis, SearchInfo};
+use crate::player_random;
+use crate::review::{self, Report};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Player {
+    Random,
+    /// min max with this search depth
+    MinMax(usize),
+}
+
+/// depth limit of a search which is limited by time
+const TIMED_MAX_DEPTH: usize = 64;
+
+enum Event {
+    /// one more depth is searched
+    Info(SearchInfo),
+    /// whole turn, empty if there are no moves
+    Done(Vec<Move>),
+}
+
+/// search of a whole turn, it is stopped when the thinker is dropped
+pub struct Thinker {
+    /// last finished depth of the current ply
+    pub info: Option<SearchInfo>,
+    pub started: Instant,
+    stop: Arc<AtomicBool>,
+    events: Receiver<Event>,
+}
+
+impl Thinker {
+    /// with `time` the min max player searches as deep as it can in this time, instead of its depth
+    pub fn start(player: Player, board: Board, time: Option<Duration>) -> Thinker {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, events) = mpsc::channel();
+        let thread_stop = stop.clone();
+        let deadline = time.map(|time| Instant::now() + time);
+        thread::spawn(move || think(player, board, deadline, &thread_stop, &sender));
+        Thinker {info: None, started: Instant::now(), stop, events}
+    }
+
+    /// moves of the turn when they are found, never blocks
+    pub fn poll(&mut self) -> Option<Vec<Move>> {
+        loop {
+            match self.events.try_recv() {
+                Ok(Event::Info(info)) => self.info = Some(info),
+                Ok(Event::Done(mvs)) => return Some(mvs),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Thinker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// search which goes deeper until it is dropped, for analysis of a position
+pub struct Analyzer {
+    /// last finished depth
+    pub analysis: Option<Analysis>,
+    stop: Arc<AtomicBool>,
+    events: Receiver<Analysis>,
+}
+
+impl Analyzer {
+    /// `lines` best moves of the position
+    pub fn start(board: Board, lines: usize) -> Analyzer {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, events) = mpsc::channel();
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            player_minmax::analyze(&board, TIMED_MAX_DEPTH, lines, &thread_stop, |analysis| {
+                let _ = sender.send(analysis.clone());
+            });
+        });
+        Analyzer {analysis: None, stop, events}
+    }
+
+    /// take the deepest analysis, never blocks
+    pub fn poll(&mut self) {
+        while let Ok(analysis) = self.events.try_recv() {
+            self.analysis = Some(analysis);
+        }
+    }
+}
+
+impl Drop for Analyzer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+enum ReviewEvent {
+    /// amount of reviewed and all turns
+    Progress(usize, usize),
+    Done(Report),
+}
+
+/// review of every turn of a game, it is stopped when the reviewer is dropped
+pub struct Reviewer {
+    /// amount of reviewed and all turns
+    pub progress: (usize, usize),
+    pub report: Option<Report>,
+    stop: Arc<AtomicBool>,
+    events: Receiver<ReviewEvent>,
+}
+
+impl Reviewer {
+    /// turns of the current line of `game` are searched with `depth`
+    pub fn start(game: Game, depth: usize) -> Reviewer {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, events) = mpsc::channel();
+        let thread_stop = stop.clone();
+        let total = game.turns().len();
+        thread::spawn(move || {
+            let report = review::review(&game, depth, &thread_stop, |done, total| {
+                let _ = sender.send(ReviewEvent::Progress(done, total));
+            });
+            if let Some(report) = report {
+                let _ = sender.send(ReviewEvent::Done(report));
+            }
+        });
+        Reviewer {progress: (0, total), report: None, stop, events}
+    }
+
+    /// take the progress and the report, never blocks
+    pub fn poll(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                ReviewEvent::Progress(done, total) => self.progress = (done, total),
+                ReviewEvent::Done(report) => self.report = Some(report),
+            }
+        }
+    }
+}
+
+impl Drop for Reviewer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// every jump of a multi-jump is searched again, so every ply can be stopped
+fn think(player: Player, mut board: Board, deadline: Option<Instant>, stop: &AtomicBool, events: &Sender<Event>) {
+    let side = board.who_turn();
+    let mut mvs = vec![];
+    while board.who_turn() == side && !board.is_ended() {
+        let mv = match player {
+            Player::Random => player_random::chouse_move(&mut board.clone()),
+            Player::MinMax(depth) => {
+                let depth = match deadline {
+                    Some(_) => TIMED_MAX_DEPTH,
+                    None => depth,
+                };
+                let info = player_minmax::search(&board, depth, deadline, stop, |info| {
+                    let _ = events.send(Event::Info(*info));
+                });
+                // out of time before the first depth, any move is better than a loss on time
+                info.and_then(|info| info.mv).or_else(|| board.clone().all_available_moves().first().copied())
+            },
+        };
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        match mv {
+            Some(mv) if board.do_move(mv).is_ok() => mvs.push(mv),
+            _ => break,
+        }
+    }
+    let _ = events.send(Event::Done(mvs));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// like `poll`, but waits up to `timeout` for the turn
+    fn wait(thinker: &mut Thinker, timeout: Duration) -> Option<Vec<Move>> {
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if let Some(mvs) = thinker.poll() {
+                return Some(mvs);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        None
+    }
+
+    #[test]
+    fn thinks_whole_turn() {
+        let mut thinker = Thinker::start(Player::MinMax(3), Board::new(), None);
+        let mvs = wait(&mut thinker, Duration::from_secs(10)).unwrap();
+        assert_eq!(mvs.len(), 1);
+        assert_eq!(thinker.info.map(|info| info.depth), Some(3));
+        assert!(Board::new().all_available_moves().contains(&mvs[0]));
+
+        let mut thinker = Thinker::start(Player::Random, Board::new(), None);
+        assert_eq!(wait(&mut thinker, Duration::from_secs(10)).map(|mvs| mvs.len()), Some(1));
+    }
+
+    #[test]
+    fn thinks_in_time() {
+        let start = Instant::now();
+        let mut thinker = Thinker::start(Player::MinMax(1), Board::new(), Some(Duration::from_millis(300)));
+        assert_eq!(wait(&mut thinker, Duration::from_secs(10)).map(|mvs| mvs.len()), Some(1));
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(thinker.info.is_some_and(|info| info.depth > 1));
+    }
+
+    #[test]
+    fn analyzer_goes_deeper() {
+        let mut analyzer = Analyzer::start(Board::new(), 2);
+        let start = Instant::now();
+        while analyzer.analysis.as_ref().map(|analysis| analysis.depth).unwrap_or(0) < 3 {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            analyzer.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(analyzer.analysis.as_ref().unwrap().lines.len(), 2);
+    }
+
+    #[test]
+    fn reviewer_reports_every_turn() {
+        let mut game = Game::new();
+        game.do_move(Board::new().all_available_moves()[0]).unwrap();
+        let mut reviewer = Reviewer::start(game, 2);
+        let start = Instant::now();
+        while reviewer.report.is_none() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            reviewer.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(reviewer.progress, (1, 1));
+        assert_eq!(reviewer.report.as_ref().unwrap().turns.len(), 1);
+    }
+
+    #[test]
+    fn dropped_thinker_stops() {
+        let thinker = Thinker::start(Player::MinMax(30), Board::new(), None);
+        let stop = thinker.stop.clone();
+        drop(thinker);
+        assert!(stop.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,49 @@
+//! images of the pieces built into the binary, so the window shows them from any working directory
+
+/// images by their paths in the piece sets
+const PIECES: [(&str, &[u8]); 8] = [
+    ("classic/white_checker.png", include_bytes!("../assets/white_checker.png")),
+    ("classic/white_king.png", include_bytes!("../assets/white_king.png")),
+    ("classic/black_checker.png", include_bytes!("../assets/black_checker.png")),
+    ("classic/black_king.png", include_bytes!("../assets/black_king.png")),
+    ("flat/white_checker.svg", include_bytes!("../assets/flat/white_checker.svg")),
+    ("flat/white_king.svg", include_bytes!("../assets/flat/white_king.svg")),
+    ("flat/black_checker.svg", include_bytes!("../assets/flat/black_checker.svg")),
+    ("flat/black_king.svg", include_bytes!("../assets/flat/black_king.svg")),
+];
+
+/// uri of a built in image like "flat/white_king.svg", the extension tells the loader its format
+pub fn uri(path: &str) -> String {
+    format!("bytes://pieces/{}", path)
+}
+
+/// built in images are given to the bytes loader of egui, before the first frame
+pub fn install(ctx: &egui::Context) {
+    for (path, bytes) in PIECES {
+        ctx.include_bytes(uri(path), bytes);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Cell;
+    use crate::theme::PieceSet;
+
+    #[test]
+    fn every_piece_is_built_in() {
+        let uris: Vec<String> = PIECES.iter().map(|(path, _)| uri(path)).collect();
+        for pieces in PieceSet::ALL {
+            for cell in [Cell::White, Cell::WhiteKing, Cell::Black, Cell::BlackKing] {
+                assert!(uris.contains(&pieces.uri(cell).unwrap()));
+            }
+        }
+        for (path, bytes) in PIECES {
+            match path.ends_with(".svg") {
+                true => assert!(bytes.starts_with(b"<svg")),
+                false => assert!(bytes.starts_with(b"\x89PNG")),
+            }
+        }
+    }
+}
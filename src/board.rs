@@ -1,5 +1,7 @@
+use std::error;
 use std::fmt;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Cell {
     White,
@@ -9,35 +11,162 @@ pub enum Cell {
     Empty,
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
-pub struct Point {
-    pub x: usize,
-    pub y: usize,
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SquareError {
+    OutOfBounds,
+    NotPlayableCell,
+}
+
+impl fmt::Display for SquareError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SquareError::OutOfBounds => write!(f, "cell is out of the board"),
+            SquareError::NotPlayableCell => write!(f, "pieces can be only on dark cells"),
+        }
+    }
+}
+
+impl error::Error for SquareError {}
+
+/// dark cell of the board, the only kind of cells pieces can stay on
+///
+/// squares are numbered from 1 to 32 starting from black's side, like in checkers notation
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "u8", into = "u8"))]
+pub struct Square {
+    x: u8,
+    y: u8,
+}
+
+impl Square {
+    pub fn new(x: usize, y: usize) -> Result<Self, SquareError> {
+        if x > 7 || y > 7 {
+            return Err(SquareError::OutOfBounds);
+        }
+        if !Square::is_playable(x, y) {
+            return Err(SquareError::NotPlayableCell);
+        }
+        Ok(Square::new_unchecked(x, y))
+    }
+
+    pub fn from_number(number: usize) -> Result<Self, SquareError> {
+        if !(1..=32).contains(&number) {
+            return Err(SquareError::OutOfBounds);
+        }
+        let y = (number - 1) / 4;
+        let x = (number - 1) % 4 * 2 + (1 - y % 2);
+        Ok(Square::new_unchecked(x, y))
+    }
+
+    fn new_unchecked(x: usize, y: usize) -> Self {
+        Square {x: x as u8, y: y as u8}
+    }
+
+    pub fn is_playable(x: usize, y: usize) -> bool {
+        !(9 * y + x).is_multiple_of(2)
+    }
+
+    pub fn x(&self) -> usize {
+        self.x as usize
+    }
+
+    pub fn y(&self) -> usize {
+        self.y as usize
+    }
+
+    pub fn number(&self) -> usize {
+        self.y() * 4 + self.x() / 2 + 1
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/// square number from 1 to 32
+impl TryFrom<u8> for Square {
+    type Error = SquareError;
+
+    fn try_from(number: u8) -> Result<Self, SquareError> {
+        Square::from_number(number as usize)
+    }
 }
 
-impl Point {
-    pub fn new(x: usize, y: usize) -> Self {
-        return Self {x, y}
+impl From<Square> for u8 {
+    fn from(square: Square) -> u8 {
+        square.number() as u8
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Move {
-    pub from: Point,
-    pub to: Point,
+    pub from: Square,
+    pub to: Square,
 }
 
 impl Move {
-    pub fn new(from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Self {
+    #[allow(dead_code)]
+    pub fn new(from: Square, to: Square) -> Self {
+        Move {from, to}
+    }
+
+    /// coordinates have to be on the board and playable
+    fn from_coords(from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Self {
         Move {
-            from: Point {x: from_x, y: from_y},
-            to: Point {x: to_x, y: to_y},
+            from: Square::new_unchecked(from_x, from_y),
+            to: Square::new_unchecked(to_x, to_y),
         }
     }
 }
 
 type Moves = Vec<Move>;
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[allow(dead_code)]
+pub enum MoveError {
+    GameEnded,
+    EmptyCell,
+    WrongSide,
+    NotPlayableCell,
+    CaptureIsMandatory,
+    /// piece which jumped on previous move has to continue jumping
+    MustContinueJump(Square),
+    DestinationOccupied,
+    OutOfBounds,
+    /// piece can't reach destination with one move
+    Unreachable,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MoveError::GameEnded => write!(f, "game is already ended"),
+            MoveError::EmptyCell => write!(f, "there is no piece to move"),
+            MoveError::WrongSide => write!(f, "it is not this piece's turn"),
+            MoveError::NotPlayableCell => write!(f, "pieces can be only on dark cells"),
+            MoveError::CaptureIsMandatory => write!(f, "capture is mandatory"),
+            MoveError::MustContinueJump(p) => write!(f, "must continue jump with piece at {}", p),
+            MoveError::DestinationOccupied => write!(f, "destination is occupied"),
+            MoveError::OutOfBounds => write!(f, "cell is out of the board"),
+            MoveError::Unreachable => write!(f, "piece can't move there"),
+        }
+    }
+}
+
+impl error::Error for MoveError {}
+
+impl From<SquareError> for MoveError {
+    fn from(e: SquareError) -> Self {
+        match e {
+            SquareError::OutOfBounds => MoveError::OutOfBounds,
+            SquareError::NotPlayableCell => MoveError::NotPlayableCell,
+        }
+    }
+}
+
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -50,30 +179,94 @@ impl fmt::Display for Cell {
     }
 }
 
-#[derive(Copy, Clone)]
+/// half moves in a row made only by kings, without captures, after which game is a draw
+const KING_MOVES_LIMIT: usize = 30;
+/// half moves three (or more) kings have to catch a lone enemy king
+const LONE_KING_MOVES_LIMIT: usize = 30;
+/// half moves without captures and promotions in kings endgames with 4 and 5 pieces
+const SMALL_KING_ENDGAME_MOVES_LIMIT: usize = 60;
+/// half moves without captures and promotions in kings endgames with 6 and 7 pieces
+const BIG_KING_ENDGAME_MOVES_LIMIT: usize = 120;
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DrawReason {
+    /// same position with the same side to move occurred three times, `Game` finds it
+    #[allow(dead_code)]
+    Repetition,
+    /// both players moved only kings without captures for 15 moves
+    KingMoves,
+    /// three or more kings didn't catch a lone king in 15 moves
+    LoneKing,
+    /// balance of forces didn't change for too long when both players have kings
+    KingEndgame,
+    #[allow(dead_code)]
+    Agreement,
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DrawReason::Repetition => write!(f, "threefold repetition"),
+            DrawReason::KingMoves => write!(f, "15 moves only by kings"),
+            DrawReason::LoneKing => write!(f, "lone king wasn't caught in 15 moves"),
+            DrawReason::KingEndgame => write!(f, "no progress in kings endgame"),
+            DrawReason::Agreement => write!(f, "agreement"),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum State {
     WhiteTurn,
     BlackTurn,
     WhiteWin,
     BlackWin,
-    Draw,
+    Draw(DrawReason),
 }
 
-#[derive(PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Side {
     White,
     Black,
 }
 
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Side::White => write!(f, "white"),
+            Side::Black => write!(f, "black"),
+        }
+    }
+}
+
+impl std::ops::Not for Side {
+    type Output = Side;
+
+    fn not(self) -> Side {
+        match self {
+            Side::White => Side::Black,
+            Side::Black => Side::White,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "serde_repr::BoardRepr", into = "serde_repr::BoardRepr"))]
 #[derive(Copy, Clone)]
 pub struct Board {
     field: [[Cell; 8]; 8],
     state: State,
     pub move_amount: usize,
-    prev_turn_jump: Option<Point>,
+    prev_turn_jump: Option<Square>,
     available_moves_exists: Option<bool>,
     white_amount: usize,
     black_amount: usize,
+    /// half moves in a row made by kings without captures
+    king_moves_in_a_row: usize,
+    /// half moves since the last capture or promotion
+    moves_since_balance_changed: usize,
 }
 
 impl Board {
@@ -85,6 +278,8 @@ impl Board {
             available_moves_exists: None,
             white_amount: 12,
             black_amount: 12,
+            king_moves_in_a_row: 0,
+            moves_since_balance_changed: 0,
             field: [
                 [Cell::Empty, Cell::Black, Cell::Empty, Cell::Black, Cell::Empty, Cell::Black, Cell::Empty, Cell::Black],
                 [Cell::Black, Cell::Empty, Cell::Black, Cell::Empty, Cell::Black, Cell::Empty, Cell::Black, Cell::Empty],
@@ -98,69 +293,75 @@ impl Board {
         }
     }
 
+    /// rows from black's side, 'w', 'b', 'W', 'B' are pieces, anything else is an empty cell
     #[allow(dead_code)]
-    pub fn from_arr(state: State, arr: [[char; 8]; 8]) -> Self {
-        Board {
-            state,
-            move_amount: 0,
-            prev_turn_jump: None,
-            available_moves_exists: None,
-            white_amount: 12,
-            black_amount: 12,
-            field: arr.map(|row| row.map(|x| match x {
-                'b' => Cell::Black,
-                'w' => Cell::White,
-                'W' => Cell::WhiteKing,
-                'B' => Cell::BlackKing,
-                _ => Cell::Empty,
-            }))
+    pub fn from_arr(side: Side, arr: [[char; 8]; 8]) -> Result<Self, SetupError> {
+        let mut builder = BoardBuilder::new().side(side);
+        for (y, row) in arr.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                let cell = match c {
+                    'b' => Cell::Black,
+                    'w' => Cell::White,
+                    'W' => Cell::WhiteKing,
+                    'B' => Cell::BlackKing,
+                    _ => continue,
+                };
+                match Square::new(x, y) {
+                    Ok(square) => builder = builder.piece(square, cell),
+                    Err(_) => return Err(SetupError::PieceOnLightCell(x, y)),
+                }
+            }
         }
+        builder.build()
     }
 
-    pub fn get_cell(&self, x: usize, y: usize) -> Cell {
-        self.field[y][x]
+    pub fn state(&self) -> State {
+        self.state
     }
 
-    pub fn is_playable_cell(&self, x: usize, y: usize) -> bool {
-        (9 * y + x) % 2 != 0
+    /// piece which jumped on previous move and has to continue jumping
+    #[allow(dead_code)]
+    pub fn jumping_piece(&self) -> Option<Square> {
+        self.prev_turn_jump
     }
 
-    fn add_checker_jump_move_if_awailabel(&self, moves: &mut Moves, mv: Move) {
-        match self.field[mv.to.y][mv.to.x] {
-            Cell::Empty => match self.field[mv.from.y][mv.from.x] {
-                Cell::White => match self.field[(mv.from.y + mv.to.y) / 2][(mv.from.x + mv.to.x) / 2] {
-                    Cell::Black => moves.push(mv),
-                    Cell::BlackKing => moves.push(mv),
-                    _ => (),
-                },
-                Cell::Black => match self.field[(mv.from.y + mv.to.y) / 2][(mv.from.x + mv.to.x) / 2] {
-                    Cell::White => moves.push(mv),
-                    Cell::WhiteKing => moves.push(mv),
-                    _ => (),
-                },
-                _ => (),
-            },
-            _ => (),
+    #[allow(dead_code)]
+    pub fn get_cell(&self, square: Square) -> Cell {
+        self.field[square.y()][square.x()]
+    }
 
+    fn add_checker_jump_move_if_awailabel(&self, moves: &mut Moves, mv: Move) {
+        if self.field[mv.to.y()][mv.to.x()] != Cell::Empty {
+            return;
+        }
+        let middle = self.field[(mv.from.y() + mv.to.y()) / 2][(mv.from.x() + mv.to.x()) / 2];
+        let is_enemy = match self.field[mv.from.y()][mv.from.x()] {
+            Cell::White => matches!(middle, Cell::Black | Cell::BlackKing),
+            Cell::Black => matches!(middle, Cell::White | Cell::WhiteKing),
+            _ => false,
+        };
+        if is_enemy {
+            moves.push(mv);
         }
     }
 
     fn add_jump_moves_for_checker(&self, moves: &mut Moves, x: usize, y: usize) {
         if x > 1 && y > 1 {
-            self.add_checker_jump_move_if_awailabel(moves, Move::new(x, y, x - 2, y - 2));
+            self.add_checker_jump_move_if_awailabel(moves, Move::from_coords(x, y, x - 2, y - 2));
         }
         if x > 1 && y < 6 {
-            self.add_checker_jump_move_if_awailabel(moves, Move::new(x, y, x - 2, y + 2));
+            self.add_checker_jump_move_if_awailabel(moves, Move::from_coords(x, y, x - 2, y + 2));
         }
         if x < 6 && y > 1 {
-            self.add_checker_jump_move_if_awailabel(moves, Move::new(x, y, x + 2, y - 2));
+            self.add_checker_jump_move_if_awailabel(moves, Move::from_coords(x, y, x + 2, y - 2));
         }
         if x < 6 && y < 6 {
-            self.add_checker_jump_move_if_awailabel(moves, Move::new(x, y, x + 2, y + 2));
+            self.add_checker_jump_move_if_awailabel(moves, Move::from_coords(x, y, x + 2, y + 2));
         }
     }
 
     // return true if jump possible available in same direction, otherwise false
+    #[allow(clippy::too_many_arguments)]
     fn add_jump_move_for_king(&self, moves: &mut Moves, x: usize, y: usize, check_x: usize, check_y: usize, dir_x: i32, dir_y: i32) -> bool {
         let enemy_checker = match self.field[y][x] {
             Cell::White => Cell::Black,
@@ -185,8 +386,8 @@ impl Board {
                 loop {
                     if (dir_x > 0 && jump_end_x >= 7) ||
                        (dir_y > 0 && jump_end_y >= 7) ||
-                       (dir_x < 0 && jump_end_x <= 0) ||
-                       (dir_y < 0 && jump_end_y <= 0) {
+                       (dir_x < 0 && jump_end_x == 0) ||
+                       (dir_y < 0 && jump_end_y == 0) {
                         break;
                     }
                     jump_end_x = (jump_end_x as i32 + dir_x) as usize;
@@ -194,7 +395,7 @@ impl Board {
 
                     match self.field[jump_end_y][jump_end_x] {
                         Cell::Empty => {
-                            moves.push(Move::new(x, y, jump_end_x, jump_end_y));
+                            moves.push(Move::from_coords(x, y, jump_end_x, jump_end_y));
                         },
                         _ => break,
                     };
@@ -287,17 +488,15 @@ impl Board {
     fn add_normal_moves_for_checker(&self, moves: &mut Moves, x: usize, y: usize, to_y: usize) {
         if x > 0 {  // not beside left border
             let to_x = x - 1;
-            match self.field[to_y][to_x] {
-                Cell::Empty => moves.push(Move::new(x, y, to_x, to_y)),
-                _ => (),
+            if self.field[to_y][to_x] == Cell::Empty {
+                moves.push(Move::from_coords(x, y, to_x, to_y));
             }
         }
 
         if x < 7 {  // not beside right border
             let to_x = x + 1;
-            match self.field[to_y][to_x] {
-                Cell::Empty => moves.push(Move::new(x, y, to_x, to_y)),
-                _ => (),
+            if self.field[to_y][to_x] == Cell::Empty {
+                moves.push(Move::from_coords(x, y, to_x, to_y));
             }
         }
     }
@@ -312,7 +511,7 @@ impl Board {
             to_x += 1;
             to_y += 1;
             match self.field[to_y][to_x] {
-                Cell::Empty => moves.push(Move::new(x, y, to_x, to_y)),
+                Cell::Empty => moves.push(Move::from_coords(x, y, to_x, to_y)),
                 _ => break,
             }
         }
@@ -326,7 +525,7 @@ impl Board {
             to_x -= 1;
             to_y -= 1;
             match self.field[to_y][to_x] {
-                Cell::Empty => moves.push(Move::new(x, y, to_x, to_y)),
+                Cell::Empty => moves.push(Move::from_coords(x, y, to_x, to_y)),
                 _ => break,
             }
         }
@@ -340,7 +539,7 @@ impl Board {
             to_x += 1;
             to_y -= 1;
             match self.field[to_y][to_x] {
-                Cell::Empty => moves.push(Move::new(x, y, to_x, to_y)),
+                Cell::Empty => moves.push(Move::from_coords(x, y, to_x, to_y)),
                 _ => break,
             }
         }
@@ -354,7 +553,7 @@ impl Board {
             to_x -= 1;
             to_y += 1;
             match self.field[to_y][to_x] {
-                Cell::Empty => moves.push(Move::new(x, y, to_x, to_y)),
+                Cell::Empty => moves.push(Move::from_coords(x, y, to_x, to_y)),
                 _ => break,
             }
         }
@@ -386,7 +585,7 @@ impl Board {
 
     fn add_forced_moves_for_all_checkers_and_kings(&self, moves: &mut Moves) {
         match self.prev_turn_jump {
-            Some(p) => self.add_jump_moves_for_checker_or_king(moves, p.x, p.y),
+            Some(p) => self.add_jump_moves_for_checker_or_king(moves, p.x(), p.y()),
             None => {
                 for y in 0..self.field.len() {
                     for x in 0..self.field[y].len() {
@@ -397,7 +596,79 @@ impl Board {
         }
     }
 
-    fn update_after_move(&mut self) {
+    /// all playable cells packed by 3 bits and side to move, same keys are the same position
+    #[allow(dead_code)]
+    pub fn position_key(&self) -> u128 {
+        let mut key: u128 = match self.state {
+            State::BlackTurn => 1,
+            _ => 0,
+        };
+        for y in 0..8 {
+            for x in ((1 - y % 2)..8).step_by(2) {
+                key = key << 3 | match self.field[y][x] {
+                    Cell::Empty => 0,
+                    Cell::White => 1,
+                    Cell::Black => 2,
+                    Cell::WhiteKing => 3,
+                    Cell::BlackKing => 4,
+                };
+            }
+        }
+        key
+    }
+
+    /// half moves since the last capture or man move, positions before them can't come again
+    #[allow(dead_code)]
+    pub fn reversible_moves(&self) -> usize {
+        self.king_moves_in_a_row
+    }
+
+    fn update_draw_counters(&mut self, moved: Cell, is_it_was_jump: bool, is_it_was_promotion: bool) {
+        let is_it_was_king = moved == Cell::WhiteKing || moved == Cell::BlackKing;
+        if is_it_was_jump || !is_it_was_king {
+            self.king_moves_in_a_row = 0;
+        } else {
+            self.king_moves_in_a_row += 1;
+        }
+
+        if is_it_was_jump || is_it_was_promotion {
+            self.moves_since_balance_changed = 0;
+        } else {
+            self.moves_since_balance_changed += 1;
+        }
+    }
+
+    fn find_draw_reason(&self) -> Option<DrawReason> {
+        if self.moves_since_balance_changed >= LONE_KING_MOVES_LIMIT {
+            let white = self.count_on_field(Cell::White);
+            let black = self.count_on_field(Cell::Black);
+            let white_kings = self.count_on_field(Cell::WhiteKing);
+            let black_kings = self.count_on_field(Cell::BlackKing);
+
+            if (white_kings >= 3 && black == 0 && black_kings == 1) ||
+               (black_kings >= 3 && white == 0 && white_kings == 1) {
+                return Some(DrawReason::LoneKing);
+            }
+
+            let pieces = white + black + white_kings + black_kings;
+            let limit = match pieces {
+                4 | 5 => SMALL_KING_ENDGAME_MOVES_LIMIT,
+                6 | 7 => BIG_KING_ENDGAME_MOVES_LIMIT,
+                _ => usize::MAX,
+            };
+            if white_kings > 0 && black_kings > 0 && self.moves_since_balance_changed >= limit {
+                return Some(DrawReason::KingEndgame);
+            }
+        }
+
+        if self.king_moves_in_a_row >= KING_MOVES_LIMIT {
+            return Some(DrawReason::KingMoves);
+        }
+
+        None
+    }
+
+    fn update_after_move(&mut self, moved: Cell, is_it_was_jump: bool) {
         if self.white_amount == 0 {
             self.state = State::BlackWin;
         }
@@ -405,10 +676,6 @@ impl Board {
             self.state = State::WhiteWin;
         }
 
-        if self.move_amount > 100 {
-            self.state = State::Draw;
-        }
-
         match self.available_moves_exists {
             Some(exists) => {
                 if !exists {
@@ -421,7 +688,7 @@ impl Board {
             },
             None => {
                 let all_mvs = self.all_available_moves();
-                if all_mvs.len() == 0 {
+                if all_mvs.is_empty() {
                     match self.state {
                         State::WhiteTurn => self.state = State::BlackWin,
                         State::BlackTurn => self.state = State::WhiteWin,
@@ -431,34 +698,39 @@ impl Board {
             },
         }
 
+        let mut is_it_was_promotion = false;
         for x in 0..self.field[0].len() {
-            match self.field[0][x] {
-                Cell::White => {
-                    self.field[0][x] = Cell::WhiteKing;
-                },
-                _ => (),
+            if self.field[0][x] == Cell::White {
+                self.field[0][x] = Cell::WhiteKing;
+                is_it_was_promotion = true;
             }
         }
         for x in 0..self.field[7].len() {
-            match self.field[7][x] {
-                Cell::Black => {
-                    self.field[7][x] = Cell::BlackKing;
-                }
-                _ => (),
+            if self.field[7][x] == Cell::Black {
+                self.field[7][x] = Cell::BlackKing;
+                is_it_was_promotion = true;
+            }
+        }
+
+        self.update_draw_counters(moved, is_it_was_jump, is_it_was_promotion);
+        if self.prev_turn_jump.is_none() && !self.is_ended() {
+            if let Some(reason) = self.find_draw_reason() {
+                self.state = State::Draw(reason);
             }
         }
     }
 
-    pub fn available_moves_for_cell(&self, x: usize, y: usize) -> Moves {
+    #[allow(dead_code)]
+    pub fn available_moves_for_cell(&self, square: Square) -> Moves {
+        let (x, y) = (square.x(), square.y());
         let mut all_forced_moves = Vec::with_capacity(10);
         let mut available_moves = Vec::with_capacity(20);
         self.add_forced_moves_for_all_checkers_and_kings(&mut all_forced_moves);
-        if all_forced_moves.len() == 0 {
+        if all_forced_moves.is_empty() {
             self.add_normal_moves_for_checker_or_king(&mut available_moves, x, y);
         } else {
-            for i in 0..all_forced_moves.len() {
-                let mv = all_forced_moves[i];
-                if mv.from == Point::new(x, y) {
+            for &mv in &all_forced_moves {
+                if mv.from == square {
                     available_moves.push(mv);
                 }
             }
@@ -471,7 +743,7 @@ impl Board {
     pub fn all_available_moves(&mut self) -> Moves {
         let mut available_moves = Vec::with_capacity(40);
         self.add_forced_moves_for_all_checkers_and_kings(&mut available_moves);
-        if available_moves.len() != 0 {
+        if !available_moves.is_empty() {
             return available_moves
         }
         
@@ -481,33 +753,33 @@ impl Board {
             }
         }
         
-        self.available_moves_exists = Some(available_moves.len() > 0);
-        return available_moves
+        self.available_moves_exists = Some(!available_moves.is_empty());
+        available_moves
     }
 
     // return if it is a jump
     fn _do_move(&mut self, mv: Move) -> bool {
         let mut is_it_was_jump = false;
 
-        self.field[mv.to.y][mv.to.x] = self.field[mv.from.y][mv.from.x];
+        self.field[mv.to.y()][mv.to.x()] = self.field[mv.from.y()][mv.from.x()];
 
-        let dir_y = match mv.to.y > mv.from.y {
+        let dir_y = match mv.to.y() > mv.from.y() {
             true => 1,
             false => -1,
         };
-        let dir_x = match mv.to.x > mv.from.x {
+        let dir_x = match mv.to.x() > mv.from.x() {
             true => 1,
             false => -1,
         };
 
-        let mut y = mv.from.y;
-        let mut x = mv.from.x;
+        let mut y = mv.from.y();
+        let mut x = mv.from.x();
         loop {
-            if y == mv.to.y || x == mv.to.x {
+            if y == mv.to.y() || x == mv.to.x() {
                 break;
             }
 
-            if self.field[y][x] != Cell::Empty && (y != mv.from.y || x != mv.from.x) {
+            if self.field[y][x] != Cell::Empty && (y != mv.from.y() || x != mv.from.x()) {
                 is_it_was_jump = true;
             }
             self.field[y][x] = Cell::Empty;
@@ -524,19 +796,20 @@ impl Board {
             }
         }
 
-        return is_it_was_jump
+        is_it_was_jump
     }
 
     pub fn do_move_without_checks(&mut self, mv: Move) {
+        let moved = self.field[mv.from.y()][mv.from.x()];
         let is_it_was_jump = self._do_move(mv);
 
         let mut forced_to_jump_on_next_turn = false;
         self.prev_turn_jump = None;
         if is_it_was_jump {
             let mut jump_moves = vec![];
-            self.add_jump_moves_for_checker_or_king(&mut jump_moves, mv.to.x, mv.to.y);
+            self.add_jump_moves_for_checker_or_king(&mut jump_moves, mv.to.x(), mv.to.y());
 
-            if jump_moves.len() != 0 {
+            if !jump_moves.is_empty() {
                 self.prev_turn_jump = Some(mv.to);
                 forced_to_jump_on_next_turn = true;
             }
@@ -551,54 +824,71 @@ impl Board {
         }
 
         self.move_amount += 1;
-        self.update_after_move();
+        // moves found before this move are not the moves of the new position
+        self.available_moves_exists = None;
+        self.update_after_move(moved, is_it_was_jump);
     }
 
-    pub fn do_move(&mut self, mv: Move) -> Result<(), &'static str> {
+    #[allow(dead_code)]
+    fn check_move(&self, mv: Move) -> Result<(), MoveError> {
+        if self.is_ended() {
+            return Err(MoveError::GameEnded);
+        }
+        let side = match self.field[mv.from.y()][mv.from.x()] {
+            Cell::Empty => return Err(MoveError::EmptyCell),
+            Cell::White | Cell::WhiteKing => Side::White,
+            Cell::Black | Cell::BlackKing => Side::Black,
+        };
+        if side != self.who_turn() {
+            return Err(MoveError::WrongSide);
+        }
+
+        match self.prev_turn_jump {
+            Some(p) if p != mv.from => return Err(MoveError::MustContinueJump(p)),
+            _ => (),
+        }
+        if self.field[mv.to.y()][mv.to.x()] != Cell::Empty {
+            return Err(MoveError::DestinationOccupied);
+        }
+
         let mut available_moves = vec![];
         self.add_forced_moves_for_all_checkers_and_kings(&mut available_moves);
-        if available_moves.len() == 0 {
-            self.add_normal_moves_for_checker_or_king(&mut available_moves, mv.from.x, mv.from.y);
+        if !available_moves.is_empty() {
+            return match available_moves.contains(&mv) {
+                true => Ok(()),
+                false => Err(MoveError::CaptureIsMandatory),
+            };
         }
 
+        self.add_normal_moves_for_checker_or_king(&mut available_moves, mv.from.x(), mv.from.y());
         match available_moves.contains(&mv) {
-            true => {
-                let is_it_was_jump = self._do_move(mv);
-
-                let mut forced_to_jump_on_next_turn = false;
-                self.prev_turn_jump = None;
-                if is_it_was_jump {
-                    let mut jump_moves = vec![];
-                    self.add_jump_moves_for_checker_or_king(&mut jump_moves, mv.to.x, mv.to.y);
-
-                    if jump_moves.len() != 0 {
-                        self.prev_turn_jump = Some(mv.to);
-                        forced_to_jump_on_next_turn = true;
-                    }
-                }
-
-                if !forced_to_jump_on_next_turn {
-                    self.state = match self.state {
-                        State::WhiteTurn => State::BlackTurn,
-                        State::BlackTurn => State::WhiteTurn,
-                        _ => unreachable!(),
-                    };
-                }
-
-                self.move_amount += 1;
-                self.update_after_move();
-                Ok(())
-            },
-            false => Err("move unavailable"),
+            true => Ok(()),
+            false => Err(MoveError::Unreachable),
         }
     }
 
+    #[allow(dead_code)]
+    pub fn do_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        self.check_move(mv)?;
+        self.do_move_without_checks(mv);
+        Ok(())
+    }
+
     pub fn is_ended(&self) -> bool {
-        match self.state {
-            State::WhiteWin => true,
-            State::BlackWin => true,
-            State::Draw => true,
-            _ => false,
+        matches!(self.state, State::WhiteWin | State::BlackWin | State::Draw(_))
+    }
+
+    /// players agreed to a draw, does nothing if game is already ended
+    #[allow(dead_code)]
+    pub fn agree_draw(&mut self) {
+        self.draw(DrawReason::Agreement);
+    }
+
+    /// draw found outside of the board like a repetition, does nothing if game is already ended
+    #[allow(dead_code)]
+    pub fn draw(&mut self, reason: DrawReason) {
+        if !self.is_ended() {
+            self.state = State::Draw(reason);
         }
     }
 
@@ -624,17 +914,369 @@ impl Board {
         match cell_type {
             Cell::Black => self.black_amount,
             Cell::White => self.white_amount,
-            _ => {
-                let mut result = 0;
-                for y in 0..self.field.len() {
-                    for x in 0..self.field[y].len() {
-                        if self.field[y][x] == cell_type {
-                            result += 1;
-                        }
-                    }
+            _ => self.count_on_field(cell_type),
+        }
+    }
+
+    fn count_on_field(&self, cell_type: Cell) -> usize {
+        let mut result = 0;
+        for y in 0..self.field.len() {
+            for x in 0..self.field[y].len() {
+                if self.field[y][x] == cell_type {
+                    result += 1;
                 }
-                return result;
+            }
+        }
+        result
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SetupError {
+    /// x and y of the piece
+    PieceOnLightCell(usize, usize),
+    /// man which should have already become a king
+    ManOnPromotionRow(Square),
+    TooManyPieces(Side),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetupError::PieceOnLightCell(x, y) => write!(f, "piece at ({}, {}) is on a light cell", x, y),
+            SetupError::ManOnPromotionRow(square) => write!(f, "man at {} should be a king", square),
+            SetupError::TooManyPieces(Side::White) => write!(f, "white has more then 12 pieces"),
+            SetupError::TooManyPieces(Side::Black) => write!(f, "black has more then 12 pieces"),
+        }
+    }
+}
+
+impl error::Error for SetupError {}
+
+/// validated way to create a board with any position
+#[derive(Copy, Clone)]
+pub struct BoardBuilder {
+    field: [[Cell; 8]; 8],
+    side: Side,
+}
+
+impl BoardBuilder {
+    /// empty board with white to move
+    pub fn new() -> Self {
+        BoardBuilder {
+            field: [[Cell::Empty; 8]; 8],
+            side: Side::White,
+        }
+    }
+
+    /// put a piece, `Cell::Empty` removes it
+    pub fn piece(mut self, square: Square, cell: Cell) -> Self {
+        self.field[square.y()][square.x()] = cell;
+        self
+    }
+
+    pub fn side(mut self, side: Side) -> Self {
+        self.side = side;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn get_cell(&self, square: Square) -> Cell {
+        self.field[square.y()][square.x()]
+    }
+
+    #[allow(dead_code)]
+    pub fn who_turn(&self) -> Side {
+        self.side
+    }
+
+    /// pieces and side to move of `board`, without its history
+    #[allow(dead_code)]
+    pub fn from_board(board: &Board) -> Self {
+        let mut builder = BoardBuilder::new().side(board.who_turn());
+        builder.field = board.field;
+        builder
+    }
+
+    pub fn build(self) -> Result<Board, SetupError> {
+        let mut white_amount = 0;
+        let mut black_amount = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                match self.field[y][x] {
+                    Cell::White if y == 0 => return Err(SetupError::ManOnPromotionRow(Square::new_unchecked(x, y))),
+                    Cell::Black if y == 7 => return Err(SetupError::ManOnPromotionRow(Square::new_unchecked(x, y))),
+                    Cell::White | Cell::WhiteKing => white_amount += 1,
+                    Cell::Black | Cell::BlackKing => black_amount += 1,
+                    Cell::Empty => (),
+                }
+            }
+        }
+        if white_amount > 12 {
+            return Err(SetupError::TooManyPieces(Side::White));
+        }
+        if black_amount > 12 {
+            return Err(SetupError::TooManyPieces(Side::Black));
+        }
+
+        let mut board = Board {
+            state: match self.side {
+                Side::White => State::WhiteTurn,
+                Side::Black => State::BlackTurn,
             },
+            move_amount: 0,
+            prev_turn_jump: None,
+            available_moves_exists: None,
+            white_amount,
+            black_amount,
+            king_moves_in_a_row: 0,
+            moves_since_balance_changed: 0,
+            field: self.field,
+        };
+
+        if white_amount == 0 {
+            board.state = State::BlackWin;
+        } else if black_amount == 0 {
+            board.state = State::WhiteWin;
+        } else if board.all_available_moves().is_empty() {
+            board.state = match self.side {
+                Side::White => State::BlackWin,
+                Side::Black => State::WhiteWin,
+            };
+        }
+        Ok(board)
+    }
+}
+
+
+/// board is saved as cells and counters, so the counted draw rules keep working after loading
+#[cfg(feature = "serde")]
+mod serde_repr {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    /// increase when the fields change, old versions still have to load
+    const VERSION: u8 = 2;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum ReprError {
+        UnknownVersion(u8),
+        /// cells must be 32 chars of 'w', 'b', 'W', 'B' or 'e'
+        BadCells,
+        Setup(SetupError),
+        /// the square has no piece of the side to move which can go on jumping
+        BadJumping(Square),
+    }
+
+    impl fmt::Display for ReprError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                ReprError::UnknownVersion(v) => write!(f, "unknown board version {}", v),
+                ReprError::BadCells => write!(f, "cells must be 32 chars of w, b, W, B or e"),
+                ReprError::Setup(e) => write!(f, "{}", e),
+                ReprError::BadJumping(square) => write!(f, "piece on {} can't go on jumping", square),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct BoardRepr {
+        version: u8,
+        /// pieces on squares from 1 to 32
+        cells: String,
+        state: State,
+        move_amount: usize,
+        jumping: Option<Square>,
+        king_moves_in_a_row: usize,
+        moves_since_balance_changed: usize,
+    }
+
+    impl From<Board> for BoardRepr {
+        fn from(board: Board) -> Self {
+            let cells = (1..=32).map(|number| match board.get_cell(Square::from_number(number).unwrap()) {
+                Cell::White => 'w',
+                Cell::Black => 'b',
+                Cell::WhiteKing => 'W',
+                Cell::BlackKing => 'B',
+                Cell::Empty => 'e',
+            }).collect();
+            BoardRepr {
+                version: VERSION,
+                cells,
+                state: board.state,
+                move_amount: board.move_amount,
+                jumping: board.prev_turn_jump,
+                king_moves_in_a_row: board.king_moves_in_a_row,
+                moves_since_balance_changed: board.moves_since_balance_changed,
+            }
+        }
+    }
+
+    impl TryFrom<BoardRepr> for Board {
+        type Error = ReprError;
+
+        fn try_from(repr: BoardRepr) -> Result<Self, ReprError> {
+            // version 1 also had the positions for repetitions, they are left to `Game` now
+            if repr.version == 0 || repr.version > VERSION {
+                return Err(ReprError::UnknownVersion(repr.version));
+            }
+            if repr.cells.chars().count() != 32 {
+                return Err(ReprError::BadCells);
+            }
+
+            let mut builder = BoardBuilder::new();
+            for (i, c) in repr.cells.chars().enumerate() {
+                let cell = match c {
+                    'w' => Cell::White,
+                    'b' => Cell::Black,
+                    'W' => Cell::WhiteKing,
+                    'B' => Cell::BlackKing,
+                    'e' => continue,
+                    _ => return Err(ReprError::BadCells),
+                };
+                builder = builder.piece(Square::from_number(i + 1).unwrap(), cell);
+            }
+            // the side which lost can't move, a draw doesn't keep the side
+            let side = match repr.state {
+                State::BlackTurn | State::WhiteWin => Side::Black,
+                _ => Side::White,
+            };
+            let mut board = builder.side(side).build().map_err(ReprError::Setup)?;
+            board.move_amount = repr.move_amount;
+            board.king_moves_in_a_row = repr.king_moves_in_a_row;
+            board.moves_since_balance_changed = repr.moves_since_balance_changed;
+
+            // wins and counted draws follow from the position, other draws can't be checked
+            if !board.is_ended() {
+                board.state = match repr.state {
+                    State::Draw(reason @ (DrawReason::Agreement | DrawReason::Repetition)) => State::Draw(reason),
+                    _ => match board.find_draw_reason() {
+                        Some(reason) => State::Draw(reason),
+                        None => board.state,
+                    },
+                };
+            }
+
+            if let Some(square) = repr.jumping {
+                let mut jumps = vec![];
+                board.add_jump_moves_for_checker_or_king(&mut jumps, square.x(), square.y());
+                let own = match board.get_cell(square) {
+                    Cell::White | Cell::WhiteKing => board.state == State::WhiteTurn,
+                    Cell::Black | Cell::BlackKing => board.state == State::BlackTurn,
+                    Cell::Empty => false,
+                };
+                if !own || jumps.is_empty() {
+                    return Err(ReprError::BadJumping(square));
+                }
+                board.prev_turn_jump = Some(square);
+            }
+            board.available_moves_exists = None;
+            Ok(board)
+        }
+    }
+
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[allow(dead_code)]
+        fn sq(number: usize) -> Square {
+            Square::from_number(number).unwrap()
+        }
+
+        #[test]
+        fn small_types() {
+            assert_eq!(serde_json::to_string(&Move::new(sq(22), sq(18))).unwrap(), "{\"from\":22,\"to\":18}");
+            assert_eq!(serde_json::from_str::<Square>("33").ok(), None);
+            assert_eq!(serde_json::to_string(&State::Draw(DrawReason::Repetition)).unwrap(), "{\"Draw\":\"Repetition\"}");
+            assert_eq!(serde_json::from_str::<Side>("\"Black\"").unwrap(), Side::Black);
+            assert_eq!(serde_json::from_str::<Cell>("\"WhiteKing\"").unwrap(), Cell::WhiteKing);
+        }
+
+        #[test]
+        fn board_round_trip() {
+            let mut board = Board::new();
+            board.do_move(Move::new(sq(22), sq(18))).unwrap();
+            board.do_move(Move::new(sq(11), sq(15))).unwrap();
+            let json = serde_json::to_string(&board).unwrap();
+            assert!(json.starts_with("{\"version\":2,\"cells\":\"bbbbbbbbbbebeebeeweewewwwwwwwwww\""));
+
+            let mut loaded: Board = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded.state(), State::WhiteTurn);
+            assert_eq!(loaded.move_amount, board.move_amount);
+            assert_eq!(loaded.all_available_moves(), board.all_available_moves());
+            assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+
+            // the first version had positions for repetitions
+            let old = format!("{},\"positions\":[5]}}", json.replace("\"version\":2", "\"version\":1").trim_end_matches('}'));
+            let loaded: Board = serde_json::from_str(&old).unwrap();
+            assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        }
+
+        #[test]
+        fn jumping_piece_is_kept() {
+            let mut board = Board::from_arr(Side::White, [
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', 'b', ' ', ' ', ' ', 'b'],
+                [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ]).unwrap();
+            board.do_move(Move::new(sq(22), sq(15))).unwrap();
+            let json = serde_json::to_string(&board).unwrap();
+            let loaded: Board = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded.jumping_piece(), Some(sq(15)));
+
+            // empty square and a piece of the other side
+            for square in [1, 20] {
+                let json = json.replace("\"jumping\":15", &format!("\"jumping\":{}", square));
+                assert_eq!(serde_json::from_str::<Board>(&json).err().map(|e| e.to_string()), Some(format!("piece on {} can't go on jumping", square)));
+            }
+            // own piece which has nothing to take
+            let json = serde_json::to_string(&Board::new()).unwrap().replace("\"jumping\":null", "\"jumping\":22");
+            assert!(serde_json::from_str::<Board>(&json).is_err());
+        }
+
+        #[test]
+        fn state_follows_position() {
+            let json = serde_json::to_string(&Board::new()).unwrap();
+            assert!(json.contains("\"state\":\"WhiteTurn\""));
+            let loaded: Board = serde_json::from_str(&json.replace("\"state\":\"WhiteTurn\"", "\"state\":\"WhiteWin\"")).unwrap();
+            assert_eq!(loaded.state(), State::BlackTurn);
+
+            let won = Board::from_arr(Side::Black, [
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+                [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ]).unwrap();
+            let won = serde_json::to_string(&won).unwrap();
+            let loaded: Board = serde_json::from_str(&won.replace("\"state\":\"WhiteWin\"", "\"state\":\"BlackTurn\"")).unwrap();
+            assert_eq!(loaded.state(), State::WhiteWin);
+
+            let agreed = json.replace("\"state\":\"WhiteTurn\"", "\"state\":{\"Draw\":\"Agreement\"}");
+            assert_eq!(serde_json::from_str::<Board>(&agreed).unwrap().state(), State::Draw(DrawReason::Agreement));
+            // counters don't allow this draw
+            let king_moves = json.replace("\"state\":\"WhiteTurn\"", "\"state\":{\"Draw\":\"KingMoves\"}");
+            assert_eq!(serde_json::from_str::<Board>(&king_moves).unwrap().state(), State::WhiteTurn);
+        }
+
+        #[test]
+        fn bad_boards() {
+            let json = serde_json::to_string(&Board::new()).unwrap();
+            assert!(serde_json::from_str::<Board>(&json.replace("\"version\":2", "\"version\":3")).is_err());
+            assert!(serde_json::from_str::<Board>(&json.replace("\"version\":2", "\"version\":0")).is_err());
+            assert!(serde_json::from_str::<Board>(&json.replace("bbbb", "bbb")).is_err());
+            assert!(serde_json::from_str::<Board>(&json.replace("bbbb", "bbbx")).is_err());
         }
     }
 }
@@ -644,9 +1286,14 @@ impl Board {
 mod tests {
     use super::*;
 
+    #[allow(dead_code)]
+    fn mv(from_x: usize, from_y: usize, to_x: usize, to_y: usize) -> Move {
+        Move::new(Square::new(from_x, from_y).unwrap(), Square::new(to_x, to_y).unwrap())
+    }
+
     #[test]
     fn white_first_move() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
@@ -655,31 +1302,30 @@ mod tests {
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
             [' ', 'w', ' ', 'w', ' ', 'w', ' ', 'w'],
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(0, 5, 1, 4)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(0, 5, 1, 4)), Ok(()));
     }
 
     #[test]
     fn black_first_move() {
         let mut board = Board::new();
-        assert!(board.do_move(Move::new(0, 2, 1, 3)).is_err());
+        assert_eq!(board.do_move(mv(1, 2, 0, 3)), Err(MoveError::WrongSide));
     }
 
     #[test]
     fn empty_cell_move() {
         let mut board = Board::new();
-        assert!(board.do_move(Move::new(0, 3, 1, 4)).is_err());
+        assert_eq!(board.do_move(mv(0, 3, 1, 4)), Err(MoveError::EmptyCell));
     }
 
     #[test]
     fn move_to_white_cells() {
-        let mut board = Board::new();
-        assert!(board.do_move(Move::new(0, 5, 0, 4)).is_err());
+        assert_eq!(Square::new(0, 4), Err(SquareError::NotPlayableCell));
     }
 
     #[test]
     fn move_to_ally_checker_position() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', 'b'],
@@ -688,13 +1334,13 @@ mod tests {
             [' ', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
             [' ', 'w', ' ', 'w', ' ', 'w', ' ', 'w'],
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert!(board.do_move(Move::new(2, 5, 1, 4)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(2, 5, 1, 4)), Err(MoveError::DestinationOccupied));
     }
 
     #[test]
     fn move_to_enemy_checker_position() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', 'b'],
@@ -703,19 +1349,19 @@ mod tests {
             [' ', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
             [' ', 'w', ' ', 'w', ' ', 'w', ' ', 'w'],
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert!(board.do_move(Move::new(1, 4, 2, 3)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(1, 4, 2, 3)), Err(MoveError::DestinationOccupied));
     }
 
     #[test]
     fn move_to_self_position() {
         let mut board = Board::new();
-        assert!(board.do_move(Move::new(0, 5, 0, 5)).is_err());
+        assert_eq!(board.do_move(mv(0, 5, 0, 5)), Err(MoveError::DestinationOccupied));
     }
 
     #[test]
     fn move_back() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', 'b'],
@@ -724,19 +1370,36 @@ mod tests {
             [' ', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
             [' ', 'w', ' ', 'w', ' ', 'w', ' ', 'w'],
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert!(board.do_move(Move::new(1, 4, 0, 5)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(1, 4, 0, 5)), Err(MoveError::Unreachable));
+    }
+
+    #[test]
+    fn move_out_of_board() {
+        assert_eq!(Square::new(8, 4), Err(SquareError::OutOfBounds));
+        assert_eq!(Square::from_number(0), Err(SquareError::OutOfBounds));
+        assert_eq!(Square::from_number(33), Err(SquareError::OutOfBounds));
+    }
+
+    #[test]
+    fn square_numbers() {
+        assert_eq!(Square::from_number(1), Square::new(1, 0));
+        assert_eq!(Square::from_number(5), Square::new(0, 1));
+        assert_eq!(Square::from_number(32), Square::new(6, 7));
+        for number in 1..=32 {
+            assert_eq!(Square::from_number(number).unwrap().number(), number);
+        }
     }
 
     #[test]
     fn move_two_cells_forward() {
         let mut board = Board::new();
-        assert!(board.do_move(Move::new(0, 5, 2, 3)).is_err());
+        assert_eq!(board.do_move(mv(0, 5, 2, 3)), Err(MoveError::Unreachable));
     }
 
     #[test]
     fn can_jump() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', 'b'],
@@ -745,13 +1408,13 @@ mod tests {
             ['w', ' ', ' ', ' ', 'w', ' ', 'w', ' '],
             [' ', 'w', ' ', 'w', ' ', 'w', ' ', 'w'],
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(3, 4, 1, 2)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(3, 4, 1, 2)), Ok(()));
     }
 
     #[test]
     fn forced_to_jump() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', 'b'],
@@ -760,46 +1423,46 @@ mod tests {
             ['w', ' ', ' ', ' ', 'w', ' ', 'w', ' '],
             [' ', 'w', ' ', 'w', ' ', 'w', ' ', 'w'],
             ['w', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert!(board.do_move(Move::new(6, 5, 7, 4)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(6, 5, 7, 4)), Err(MoveError::CaptureIsMandatory));
     }
 
 
     #[test]
     fn jump_two_in_a_row() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', 'b', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', 'b', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', ' ', 'w', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
+            [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(5, 5, 3, 3)), Ok(()));
-        assert_eq!(board.do_move(Move::new(3, 3, 5, 1)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(2, 5, 4, 3)), Ok(()));
+        assert_eq!(board.do_move(mv(4, 3, 2, 1)), Ok(()));
     }
 
     #[test]
     fn jump_backwards() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', 'w', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', ' ', 'b', ' ', ' '],
+            [' ', ' ', ' ', 'w', ' ', ' ', ' ', ' '],
+            [' ', ' ', 'b', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(4, 4, 6, 6)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(3, 4, 1, 6)), Ok(()));
     }
 
     #[test]
     fn king_moves() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
-            ['W', ' ', ' ', ' ', ' ', ' ', ' ', 'b'],
+        let mut board = Board::from_arr(Side::White, [
+            [' ', 'b', ' ', ' ', ' ', ' ', ' ', 'W'],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -807,16 +1470,16 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(0, 0, 6, 6)), Ok(()));
-        assert_eq!(board.do_move(Move::new(7, 0, 6, 1)), Ok(()));
-        assert_eq!(board.do_move(Move::new(6, 6, 0, 0)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(7, 0, 1, 6)), Ok(()));
+        assert_eq!(board.do_move(mv(1, 0, 2, 1)), Ok(()));
+        assert_eq!(board.do_move(mv(1, 6, 7, 0)), Ok(()));
     }
 
     #[test]
     fn king_cant_moves() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
-            ['W', ' ', ' ', ' ', ' ', ' ', ' ', 'b'],
+        let mut board = Board::from_arr(Side::White, [
+            [' ', 'b', ' ', ' ', ' ', ' ', ' ', 'W'],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -824,13 +1487,13 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert!(board.do_move(Move::new(0, 0, 6, 7)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(7, 0, 2, 7)), Err(MoveError::Unreachable));
     }
 
     #[test]
     fn king_long_jump_one() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -839,13 +1502,13 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             ['W', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(0, 7, 7, 0)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(0, 7, 7, 0)), Ok(()));
     }
 
     #[test]
     fn king_force_jump_one() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -854,13 +1517,13 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             ['W', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert!(board.do_move(Move::new(0, 7, 1, 6)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(0, 7, 1, 6)), Err(MoveError::CaptureIsMandatory));
     }
 
     #[test]
     fn king_jump_two() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -869,14 +1532,14 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             ['W', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(0, 7, 5, 2)), Ok(()));
-        assert_eq!(board.do_move(Move::new(5, 2, 7, 4)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(0, 7, 5, 2)), Ok(()));
+        assert_eq!(board.do_move(mv(5, 2, 7, 4)), Ok(()));
     }
 
     #[test]
     fn chear_cell_after_jump_over_it() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -885,15 +1548,15 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', 'b', ' ', ' ', ' ', ' ', ' ', ' '],
             ['w', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
+        ]).unwrap();
         assert_eq!(board.field[6][1], Cell::Black);
-        assert_eq!(board.do_move(Move::new(0, 7, 2, 5)), Ok(()));
+        assert_eq!(board.do_move(mv(0, 7, 2, 5)), Ok(()));
         assert_eq!(board.field[6][1], Cell::Empty);
     }
 
     #[test]
     fn after_jump_with_one_piece_cant_eat_with_another_in_a_row() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -902,14 +1565,14 @@ mod tests {
             ['w', ' ', ' ', ' ', ' ', ' ', 'b', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', 'w'],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(7, 6, 5, 4)), Ok(()));
-        assert!(board.do_move(Move::new(0, 5, 2, 3)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(7, 6, 5, 4)), Ok(()));
+        assert_eq!(board.do_move(mv(0, 5, 2, 3)), Err(MoveError::MustContinueJump(Square::new(5, 4).unwrap())));
     }
 
     #[test]
     fn cant_jump_after_normal_move() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
@@ -918,14 +1581,14 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(3, 4, 2, 3)), Ok(()));
-        assert!(board.do_move(Move::new(2, 3, 4, 1)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(3, 4, 2, 3)), Ok(()));
+        assert_eq!(board.do_move(mv(2, 3, 4, 1)), Err(MoveError::WrongSide));
     }
 
     #[test]
     fn forced_to_jump_over_king_too() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
@@ -934,13 +1597,13 @@ mod tests {
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', 'B', ' ', ' ', ' ', ' ', ' ', ' '],
             ['w', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
-        ]);
-        assert!(board.do_move(Move::new(2, 7, 3, 6)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(2, 7, 3, 6)), Err(MoveError::CaptureIsMandatory));
     }
 
     #[test]
     fn forced_to_jump_after_enemy_double_jump() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', 'b'],
             [' ', ' ', ' ', ' ', ' ', ' ', 'b', ' '],
             [' ', 'b', ' ', 'b', ' ', 'b', ' ', 'w'],
@@ -949,15 +1612,15 @@ mod tests {
             ['w', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
             [' ', ' ', ' ', ' ', ' ', 'w', ' ', 'w'],
             [' ', ' ', 'w', ' ', 'w', ' ', 'w', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(0, 3, 2, 1)), Ok(()));
-        assert_eq!(board.do_move(Move::new(2, 1, 4, 3)), Ok(()));
-        assert!(board.do_move(Move::new(3, 0, 4, 1)).is_err());
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(0, 3, 2, 1)), Ok(()));
+        assert_eq!(board.do_move(mv(2, 1, 4, 3)), Ok(()));
+        assert_eq!(board.do_move(mv(3, 0, 4, 1)), Err(MoveError::CaptureIsMandatory));
     }
 
     #[test]
     fn dont_delete_cell_after_jump() {
-        let mut board = Board::from_arr(State::WhiteTurn, [
+        let mut board = Board::from_arr(Side::White, [
             [' ', ' ', ' ', 'b', ' ', 'b', ' ', ' '],
             [' ', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', ' ', ' ', 'w', ' ', 'b'],
@@ -966,14 +1629,14 @@ mod tests {
             ['b', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
             [' ', 'w', ' ', ' ', ' ', ' ', ' ', 'w'],
             ['w', ' ', 'w', ' ', ' ', ' ', 'w', ' '],
-        ]);
-        assert_eq!(board.do_move(Move::new(5, 2, 7, 0)), Ok(()));
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(5, 2, 7, 0)), Ok(()));
         assert_eq!(board.field[0][7], Cell::WhiteKing);
     }
 
     #[test]
     fn king_cant_jump_over_two_in_a_row() {
-        let mut board = Board::from_arr(State::BlackTurn, [
+        let mut board = Board::from_arr(Side::Black, [
             [' ', 'b', ' ', ' ', ' ', 'b', ' ', 'b'],
             ['b', ' ', 'b', ' ', 'b', ' ', 'b', ' '],
             [' ', ' ', ' ', ' ', ' ', ' ', ' ', 'b'],
@@ -982,7 +1645,227 @@ mod tests {
             [' ', ' ', ' ', ' ', 'w', ' ', ' ', ' '],
             [' ', ' ', ' ', 'w', ' ', ' ', ' ', ' '],
             ['w', ' ', 'B', ' ', 'w', ' ', 'w', ' '],
+        ]).unwrap();
+        assert_eq!(board.do_move(mv(2, 7, 6, 3)), Err(MoveError::Unreachable));
+    }
+
+    #[test]
+    fn draw_after_king_moves_in_a_row() {
+        let mut board = Board::from_arr(Side::White, [
+            [' ', 'B', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', 'w', ' ', ' '],
+            ['W', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        board.king_moves_in_a_row = KING_MOVES_LIMIT - 1;
+        assert_eq!(board.do_move(mv(0, 7, 1, 6)), Ok(()));
+        assert_eq!(board.state(), State::Draw(DrawReason::KingMoves));
+    }
+
+    #[test]
+    fn man_move_resets_king_moves_in_a_row() {
+        let mut board = Board::from_arr(Side::White, [
+            [' ', 'B', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', 'w', ' ', ' '],
+            ['W', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        board.king_moves_in_a_row = KING_MOVES_LIMIT - 1;
+        assert_eq!(board.do_move(mv(5, 6, 4, 5)), Ok(()));
+        assert_eq!(board.state(), State::BlackTurn);
+        assert_eq!(board.king_moves_in_a_row, 0);
+    }
+
+    #[test]
+    fn draw_when_lone_king_not_caught() {
+        let mut board = Board::from_arr(Side::White, [
+            [' ', 'B', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', 'W'],
+            ['W', ' ', ' ', ' ', ' ', ' ', 'W', ' '],
+        ]).unwrap();
+        board.moves_since_balance_changed = LONE_KING_MOVES_LIMIT - 1;
+        assert_eq!(board.do_move(mv(0, 7, 1, 6)), Ok(()));
+        assert_eq!(board.state(), State::Draw(DrawReason::LoneKing));
+    }
+
+    #[test]
+    fn draw_in_kings_endgame_without_progress() {
+        let mut board = Board::from_arr(Side::White, [
+            [' ', 'B', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', 'b'],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', 'w', ' ', ' '],
+            ['W', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        board.moves_since_balance_changed = SMALL_KING_ENDGAME_MOVES_LIMIT - 1;
+        assert_eq!(board.do_move(mv(5, 6, 4, 5)), Ok(()));
+        assert_eq!(board.state(), State::Draw(DrawReason::KingEndgame));
+    }
+
+    #[test]
+    fn draw_by_agreement() {
+        let mut board = Board::new();
+        board.agree_draw();
+        assert_eq!(board.state(), State::Draw(DrawReason::Agreement));
+        assert!(board.is_ended());
+        assert_eq!(board.do_move(mv(0, 5, 1, 4)), Err(MoveError::GameEnded));
+    }
+
+    #[test]
+    fn reject_piece_on_light_cell() {
+        let board = Board::from_arr(Side::White, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', 'b', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['w', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
         ]);
-        assert!(board.do_move(Move::new(2, 7, 6, 3)).is_err());
+        assert_eq!(board.err(), Some(SetupError::PieceOnLightCell(4, 4)));
+    }
+
+    #[test]
+    fn reject_man_on_promotion_row() {
+        let white = BoardBuilder::new()
+            .piece(Square::new(1, 0).unwrap(), Cell::White)
+            .piece(Square::new(0, 7).unwrap(), Cell::Black)
+            .build();
+        assert_eq!(white.err(), Some(SetupError::ManOnPromotionRow(Square::new(1, 0).unwrap())));
+
+        let black = BoardBuilder::new()
+            .piece(Square::new(1, 0).unwrap(), Cell::WhiteKing)
+            .piece(Square::new(0, 7).unwrap(), Cell::Black)
+            .build();
+        assert_eq!(black.err(), Some(SetupError::ManOnPromotionRow(Square::new(0, 7).unwrap())));
+    }
+
+    #[test]
+    fn reject_too_many_pieces() {
+        let mut builder = BoardBuilder::new().piece(Square::from_number(1).unwrap(), Cell::Black);
+        for number in 10..23 {
+            builder = builder.piece(Square::from_number(number).unwrap(), Cell::White);
+        }
+        assert_eq!(builder.build().err(), Some(SetupError::TooManyPieces(Side::White)));
+
+        let mut builder = BoardBuilder::new().piece(Square::from_number(32).unwrap(), Cell::White);
+        for number in 10..23 {
+            builder = builder.piece(Square::from_number(number).unwrap(), Cell::BlackKing);
+        }
+        assert_eq!(builder.build().err(), Some(SetupError::TooManyPieces(Side::Black)));
+    }
+
+    #[test]
+    fn builder_counts_pieces() {
+        let board = BoardBuilder::new()
+            .piece(Square::from_number(1).unwrap(), Cell::BlackKing)
+            .piece(Square::from_number(9).unwrap(), Cell::Black)
+            .piece(Square::from_number(30).unwrap(), Cell::White)
+            .build()
+            .unwrap();
+        assert_eq!(board.count(Cell::White), 1);
+        assert_eq!(board.count(Cell::Black), 2);
+        assert_eq!(board.state(), State::WhiteTurn);
+    }
+
+    #[test]
+    fn blocked_side_loses_after_move() {
+        let mut board = Board::from_arr(Side::White, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['b', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['w', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        assert_eq!(board.all_available_moves().len(), 3);
+        assert_eq!(board.do_move(mv(0, 3, 1, 2)), Ok(()));
+        assert_eq!(board.state(), State::WhiteWin);
+    }
+
+    #[test]
+    fn blocked_side_loses_after_unchecked_move() {
+        // the engine plays without checks, the cache of the moves must be reset there too
+        let mut board = Board::from_arr(Side::White, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['b', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['w', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        assert_eq!(board.all_available_moves().len(), 3);
+        board.do_move_without_checks(mv(0, 3, 1, 2));
+        assert_eq!(board.state(), State::WhiteWin);
+    }
+
+    #[test]
+    fn checked_and_unchecked_moves_agree() {
+        let start = Board::from_arr(Side::White, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', 'b'],
+            [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        let mut checked = start;
+        let mut unchecked = start;
+        for jump in [mv(2, 5, 4, 3), mv(4, 3, 2, 1)] {
+            assert_eq!(checked.do_move(jump), Ok(()));
+            unchecked.do_move_without_checks(jump);
+            assert_eq!(checked.state(), unchecked.state());
+            assert_eq!(checked.all_available_moves(), unchecked.all_available_moves());
+            for n in 1..=32 {
+                let square = Square::from_number(n).unwrap();
+                assert_eq!(checked.get_cell(square), unchecked.get_cell(square));
+            }
+        }
+        assert_eq!(checked.state(), State::BlackTurn);
+    }
+
+    #[test]
+    fn builder_detects_immediate_win() {
+        let no_black = BoardBuilder::new()
+            .piece(Square::from_number(30).unwrap(), Cell::White)
+            .side(Side::Black)
+            .build()
+            .unwrap();
+        assert_eq!(no_black.state(), State::WhiteWin);
+
+        let blocked = Board::from_arr(Side::Black, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            ['b', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', 'w', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        assert_eq!(blocked.state(), State::WhiteWin);
     }
 }
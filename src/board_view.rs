@@ -0,0 +1,220 @@
+//! geometry and animations of the painted board in the window
+
+use std::time::Instant;
+
+use egui::{Pos2, Rect};
+
+use crate::board::{Board, BoardBuilder, Cell, Move, Side, Square};
+use crate::notation;
+
+/// seconds one hop of a move slides
+pub const HOP_TIME: f32 = 0.15;
+
+/// 9x9 cells, the first row and column are coordinates, the rest are 8x8 squares
+pub struct Geometry {
+    pub rect: Rect,
+    /// black's side is at the bottom
+    pub flipped: bool,
+}
+
+impl Geometry {
+    pub fn cell_size(&self) -> f32 {
+        self.rect.width() / 9.
+    }
+
+    /// cell of the grid with coordinates, `col` and `row` are from 0 to 8
+    pub fn cell_rect(&self, col: usize, row: usize) -> Rect {
+        let size = self.cell_size();
+        let min = self.rect.min + egui::vec2(col as f32 * size, row as f32 * size);
+        Rect::from_min_size(min, egui::vec2(size, size))
+    }
+
+    /// column or row of the grid for board coordinate `i`
+    fn grid_index(&self, i: usize) -> usize {
+        match self.flipped {
+            false => i + 1,
+            true => 8 - i,
+        }
+    }
+
+    /// board coordinate for column or row `i` of the grid, None for coordinates
+    pub fn board_index(&self, i: usize) -> Option<usize> {
+        match (i, self.flipped) {
+            (0, _) | (9.., _) => None,
+            (i, false) => Some(i - 1),
+            (i, true) => Some(8 - i),
+        }
+    }
+
+    pub fn rect_at(&self, x: usize, y: usize) -> Rect {
+        self.cell_rect(self.grid_index(x), self.grid_index(y))
+    }
+
+    pub fn square_rect(&self, square: Square) -> Rect {
+        self.rect_at(square.x(), square.y())
+    }
+
+    /// board coordinates under `pos`, None outside of the 8x8 squares
+    pub fn xy_at(&self, pos: Pos2) -> Option<(usize, usize)> {
+        let offset = (pos - self.rect.min) / self.cell_size();
+        if offset.x < 0. || offset.y < 0. {
+            return None;
+        }
+        let x = self.board_index(offset.x as usize)?;
+        let y = self.board_index(offset.y as usize)?;
+        Some((x, y))
+    }
+}
+
+/// board coordinates of the keyboard cursor after a step of `dx` and `dy` on the screen, it stays on the board
+pub fn step((x, y): (usize, usize), dx: i32, dy: i32, flipped: bool) -> (usize, usize) {
+    let (dx, dy) = match flipped {
+        false => (dx, dy),
+        true => (-dx, -dy),
+    };
+    let x = (x as i32 + dx).clamp(0, 7) as usize;
+    let y = (y as i32 + dy).clamp(0, 7) as usize;
+    (x, y)
+}
+
+/// pieces which `by` can take if it moves in the position, even if it is the other side's turn
+pub fn capturable_pieces(board: &Board, by: Side) -> Vec<Square> {
+    let board = match board.who_turn() == by {
+        true => *board,
+        false => match BoardBuilder::from_board(board).side(by).build() {
+            Ok(board) => board,
+            Err(_) => return vec![],
+        },
+    };
+    let mut result = vec![];
+    if board.is_ended() {
+        return result;
+    }
+    for turn in notation::complete_turns(&board) {
+        for square in notation::captured_squares(&board, &turn) {
+            if !result.contains(&square) {
+                result.push(square);
+            }
+        }
+    }
+    result
+}
+
+/// piece which slides from one square to another
+pub struct Slide {
+    pub cell: Cell,
+    pub from: Square,
+    pub to: Square,
+    /// from 0 to 1
+    pub progress: f32,
+}
+
+/// what to paint at a moment of an animation
+pub struct Frame {
+    /// position before the current hop, `slide.from` and `fading` squares are painted separately
+    pub board: Board,
+    pub slide: Slide,
+    /// captured piece and its opacity from 1 to 0
+    pub fading: Option<(Square, Cell, f32)>,
+}
+
+/// moves which slide one hop after another
+pub struct Animation {
+    board: Board,
+    mvs: Vec<Move>,
+    started: Instant,
+}
+
+impl Animation {
+    /// `board` is the position before `mvs`
+    pub fn new(board: Board, mvs: Vec<Move>) -> Animation {
+        Animation {board, mvs, started: Instant::now()}
+    }
+
+    /// moves played while the animation runs are played after its moves
+    pub fn extend(&mut self, mvs: &[Move]) {
+        self.mvs.extend_from_slice(mvs);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame().is_none()
+    }
+
+    pub fn frame(&self) -> Option<Frame> {
+        self.frame_at(self.started.elapsed().as_secs_f32())
+    }
+
+    fn frame_at(&self, seconds: f32) -> Option<Frame> {
+        let hop = (seconds / HOP_TIME) as usize;
+        let mv = *self.mvs.get(hop)?;
+        let progress = seconds / HOP_TIME - hop as f32;
+        let mut board = self.board;
+        for &mv in &self.mvs[..hop] {
+            board.do_move_without_checks(mv);
+        }
+        let fading = notation::captured_square(&board, mv).map(|square| (square, board.get_cell(square), 1. - progress));
+        let slide = Slide {cell: board.get_cell(mv.from), from: mv.from, to: mv.to, progress};
+        Some(Frame {board, slide, fading})
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(number: usize) -> Square {
+        Square::from_number(number).unwrap()
+    }
+
+    #[test]
+    fn squares_on_screen() {
+        let rect = Rect::from_min_size(Pos2::new(10., 10.), egui::vec2(90., 90.));
+        let white = Geometry {rect, flipped: false};
+        assert_eq!(white.rect_at(0, 0), Rect::from_min_size(Pos2::new(20., 20.), egui::vec2(10., 10.)));
+        assert_eq!(white.xy_at(Pos2::new(25., 95.)), Some((0, 7)));
+        assert_eq!(white.xy_at(Pos2::new(15., 95.)), None);
+        assert_eq!(white.xy_at(Pos2::new(105., 95.)), None);
+
+        let black = Geometry {rect, flipped: true};
+        assert_eq!(black.rect_at(7, 7), Rect::from_min_size(Pos2::new(20., 20.), egui::vec2(10., 10.)));
+        assert_eq!(black.xy_at(Pos2::new(25., 95.)), Some((7, 0)));
+        assert_eq!(black.board_index(1), Some(7));
+        assert_eq!(black.xy_at(black.square_rect(sq(22)).center()), Some((sq(22).x(), sq(22).y())));
+    }
+
+    #[test]
+    fn cursor_steps() {
+        assert_eq!(step((2, 5), 1, -1, false), (3, 4));
+        assert_eq!(step((2, 5), 1, -1, true), (1, 6));
+        assert_eq!(step((0, 7), -1, 1, false), (0, 7));
+    }
+
+    #[test]
+    fn capturable() {
+        let board = notation::parse_fen("W:W22,K31:B18,10,1").unwrap();
+        assert_eq!(capturable_pieces(&board, Side::White), vec![sq(18), sq(10)]);
+        // black man on 18 takes 22 if it is black's turn
+        assert_eq!(capturable_pieces(&board, Side::Black), vec![sq(22)]);
+        assert_eq!(capturable_pieces(&Board::new(), Side::Black), vec![]);
+    }
+
+    #[test]
+    fn multi_jump_frames() {
+        let board = notation::parse_fen("W:W22:B18,10,1").unwrap();
+        let animation = Animation::new(board, vec![Move::new(sq(22), sq(15)), Move::new(sq(15), sq(6))]);
+
+        let frame = animation.frame_at(HOP_TIME * 0.5).unwrap();
+        assert_eq!((frame.slide.from, frame.slide.to), (sq(22), sq(15)));
+        assert_eq!(frame.slide.cell, Cell::White);
+        assert!((frame.slide.progress - 0.5).abs() < 0.01);
+        assert_eq!(frame.fading.map(|(square, cell, _)| (square, cell)), Some((sq(18), Cell::Black)));
+
+        let frame = animation.frame_at(HOP_TIME * 1.25).unwrap();
+        assert_eq!((frame.slide.from, frame.slide.to), (sq(15), sq(6)));
+        assert_eq!(frame.board.get_cell(sq(18)), Cell::Empty);
+        assert_eq!(frame.fading.map(|(square, _, _)| square), Some(sq(10)));
+
+        assert!(animation.frame_at(HOP_TIME * 2.).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::AtomicBool;
+
+use crate::board::{Board, Cell, Move, Side, Square, State};
+use crate::game::Game;
+use crate::notation;
+use crate::pdn;
+use crate::player_minmax;
+use crate::player_random;
+use crate::review;
+use crate::tui;
+
+pub type ChouseMove = fn(&mut Board) -> Option<Move>;
+
+const PLAY_USAGE: &str = "usage: checkers play [--opponent none|random|minmax5|minmax10|minmax15] [--side white|black] [--tui]";
+
+const REVIEW_USAGE: &str = "usage: checkers review <game.pdn> [--depth N]";
+
+const PLAY_HELP: &str = "enter moves like 22-18, 22x15x8 or c3-d4
+commands:
+  moves - show available moves
+  draw  - agree to a draw
+  quit  - stop the game";
+
+pub fn parse_side(s: &str) -> Result<Side, String> {
+    match s {
+        "white" => Ok(Side::White),
+        "black" => Ok(Side::Black),
+        _ => Err(format!("unknown side \"{}\"", s)),
+    }
+}
+
+pub fn parse_opponent(s: &str) -> Result<Option<ChouseMove>, String> {
+    match s {
+        "none" => Ok(None),
+        "random" => Ok(Some(player_random::chouse_move)),
+        "minmax5" => Ok(Some(player_minmax::chouse_move5)),
+        "minmax10" => Ok(Some(player_minmax::chouse_move10)),
+        "minmax15" => Ok(Some(player_minmax::chouse_move15)),
+        _ => Err(format!("unknown opponent \"{}\"", s)),
+    }
+}
+
+/// value of `--name value` option
+pub fn option_value<'a>(args: &'a [String], name: &str) -> Result<Option<&'a str>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => match args.get(i + 1) {
+            Some(value) => Ok(Some(value)),
+            None => Err(format!("{} needs a value", name)),
+        },
+        None => Ok(None),
+    }
+}
+
+/// board as text, `side` is at the bottom
+pub fn render_board(board: &Board, side: Side) -> String {
+    let order: Vec<usize> = match side {
+        Side::White => (0..8).collect(),
+        Side::Black => (0..8).rev().collect(),
+    };
+    let files: String = order.iter().map(|&x| format!(" {}", (b'a' + x as u8) as char)).collect();
+
+    let mut result = format!("  {}\n", files);
+    for &y in order.iter() {
+        result.push_str(&format!("{} ", 8 - y));
+        for &x in order.iter() {
+            let cell = match Square::new(x, y) {
+                Ok(square) => match board.get_cell(square) {
+                    Cell::Empty => ".".to_string(),
+                    cell => cell.to_string(),
+                },
+                Err(_) => " ".to_string(),
+            };
+            result.push_str(&format!(" {}", cell));
+        }
+        result.push_str(&format!("  {}\n", 8 - y));
+    }
+    result.push_str(&format!("  {}\n", files));
+    result
+}
+
+pub fn game_result(board: &Board) -> String {
+    match board.state() {
+        State::WhiteWin => "white won".to_string(),
+        State::BlackWin => "black won".to_string(),
+        State::Draw(reason) => format!("draw: {}", reason),
+        _ => "game is not ended".to_string(),
+    }
+}
+
+/// let `chouse_func` make all moves of the current turn, return them
+pub fn play_turn(game: &mut Game, chouse_func: ChouseMove) -> Result<Vec<Move>, Box<dyn Error>> {
+    let side = game.board().who_turn();
+    let mut mvs = vec![];
+    while game.board().who_turn() == side && !game.board().is_ended() {
+        match chouse_func(&mut game.board().clone()) {
+            Some(mv) => {
+                game.do_move(mv)?;
+                mvs.push(mv);
+            },
+            None => break,
+        }
+    }
+    Ok(mvs)
+}
+
+fn print_available_moves(board: &Board) {
+    let mvs = board.clone().all_available_moves();
+    let names: Vec<String> = mvs.iter().map(|&mv| format!(
+        "{} ({}-{})",
+        notation::format_turn(board, &[mv]),
+        notation::algebraic(mv.from),
+        notation::algebraic(mv.to),
+    )).collect();
+    println!("{}", names.join(" "));
+}
+
+/// apply moves written by a player, stop on the first unavailable one
+fn apply_text_move(game: &mut Game, text: &str) {
+    let mvs = match notation::parse_move(text) {
+        Ok(mvs) => mvs,
+        Err(e) => {
+            println!("can't read move: {}", e);
+            return;
+        },
+    };
+    for mv in mvs {
+        if let Err(e) = game.do_move(mv) {
+            println!("move unavailable: {}", e);
+            return;
+        }
+    }
+}
+
+/// line based game in the terminal against one of the players or another human
+pub fn play(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", PLAY_USAGE);
+        return Ok(());
+    }
+    let side = parse_side(option_value(args, "--side")?.unwrap_or("white"))?;
+    let opponent_name = option_value(args, "--opponent")?.unwrap_or("minmax5");
+    let opponent = parse_opponent(opponent_name)?;
+    if args.iter().any(|arg| arg == "--tui") {
+        return tui::run(opponent_name, side);
+    }
+
+    let mut game = Game::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    println!("{}", PLAY_HELP);
+
+    loop {
+        if game.board().is_ended() {
+            print!("{}", render_board(game.board(), side));
+            println!("{}", game_result(game.board()));
+            return Ok(());
+        }
+
+        let turn = game.board().who_turn();
+        if let Some(chouse_func) = opponent {
+            if turn != side {
+                let before = *game.board();
+                let mvs = play_turn(&mut game, chouse_func)?;
+                println!("{} played {}", turn, notation::format_turn(&before, &mvs));
+                continue;
+            }
+        }
+
+        print!("{}", render_board(game.board(), side));
+        print!("{} to move> ", turn);
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        match line.trim() {
+            "" => (),
+            "quit" | "exit" => return Ok(()),
+            "help" => println!("{}", PLAY_HELP),
+            "moves" => print_available_moves(game.board()),
+            "draw" => game.agree_draw(),
+            text => apply_text_move(&mut game, text),
+        }
+    }
+}
+
+/// report of the engine on the main line of a pdn game
+pub fn review(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", REVIEW_USAGE);
+        return Ok(());
+    }
+    let path = match args.first() {
+        Some(path) if !path.starts_with("--") => path,
+        _ => return Err(format!("no game file\n{}", REVIEW_USAGE).into()),
+    };
+    let depth = match option_value(args, "--depth")? {
+        Some(depth) => depth.parse().ok().filter(|&depth| depth > 0).ok_or(format!("bad depth \"{}\"", depth))?,
+        None => review::DEFAULT_DEPTH,
+    };
+    let game = pdn::read(&fs::read_to_string(path)?)?;
+    let report = review::review(&game, depth, &AtomicBool::new(false), |done, total| {
+        eprint!("\rreviewing turn {}/{}", done, total);
+    });
+    eprintln!();
+    match report {
+        Some(report) => print!("{}", report.format()),
+        None => return Err("review is stopped".into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,293 @@
+//! chess clocks, the side which runs out of time loses
+//!
+//! time is passed in as `now`, so the clock doesn't depend on the real time
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::board::Side;
+
+/// time left when the computer stops thinking, it covers the time to play the move
+const SAFETY_MARGIN: Duration = Duration::from_millis(50);
+/// turns the rest of a game is expected to take, when it isn't known
+const EXPECTED_TURNS: u32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeControl {
+    SuddenDeath {base: Duration},
+    /// `increment` is added after every turn
+    Fischer {base: Duration, increment: Duration},
+    /// time spent on a turn is given back, but not more than `delay`
+    Bronstein {base: Duration, delay: Duration},
+    /// `period` is added after every `moves` turns, the first period is given at the start
+    MovesPerPeriod {moves: usize, period: Duration},
+}
+
+impl TimeControl {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeControl::SuddenDeath {..} => "sudden-death",
+            TimeControl::Fischer {..} => "fischer",
+            TimeControl::Bronstein {..} => "bronstein",
+            TimeControl::MovesPerPeriod {..} => "moves-per-period",
+        }
+    }
+
+    /// like "fischer 300 3", seconds and amounts of moves
+    pub fn parse(s: &str) -> Option<TimeControl> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let seconds = |i: usize| words.get(i)?.parse::<u64>().ok().map(Duration::from_secs);
+        let control = match *words.first()? {
+            "sudden-death" if words.len() == 2 => TimeControl::SuddenDeath {base: seconds(1)?},
+            "fischer" if words.len() == 3 => TimeControl::Fischer {base: seconds(1)?, increment: seconds(2)?},
+            "bronstein" if words.len() == 3 => TimeControl::Bronstein {base: seconds(1)?, delay: seconds(2)?},
+            "moves-per-period" if words.len() == 3 => {
+                let moves = words[1].parse().ok().filter(|&moves| moves > 0)?;
+                TimeControl::MovesPerPeriod {moves, period: seconds(2)?}
+            },
+            _ => return None,
+        };
+        Some(control)
+    }
+
+    fn start_time(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath {base} => base,
+            TimeControl::Fischer {base, ..} => base,
+            TimeControl::Bronstein {base, ..} => base,
+            TimeControl::MovesPerPeriod {period, ..} => period,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimeControl::SuddenDeath {base} => write!(f, "{} {}", self.name(), base.as_secs()),
+            TimeControl::Fischer {base, increment: extra} | TimeControl::Bronstein {base, delay: extra} => {
+                write!(f, "{} {} {}", self.name(), base.as_secs(), extra.as_secs())
+            },
+            TimeControl::MovesPerPeriod {moves, period} => write!(f, "{} {} {}", self.name(), moves, period.as_secs()),
+        }
+    }
+}
+
+fn index(side: Side) -> usize {
+    match side {
+        Side::White => 0,
+        Side::Black => 1,
+    }
+}
+
+/// time left is like "4:05.3", minutes and seconds
+pub fn format_time(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+pub struct Clock {
+    pub control: TimeControl,
+    /// time left before the current turn
+    remaining: [Duration; 2],
+    /// finished turns of every side
+    turns: [usize; 2],
+    /// side which thinks and when it started
+    running: Option<(Side, Instant)>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let time = control.start_time();
+        Clock {control, remaining: [time, time], turns: [0, 0], running: None}
+    }
+
+    pub fn running_side(&self) -> Option<Side> {
+        self.running.map(|(side, _)| side)
+    }
+
+    /// time the running side spent on the turn, which is taken from its clock
+    fn charged(&self, side: Side, now: Instant) -> Duration {
+        let spent = match self.running {
+            Some((running, started)) if running == side => now.saturating_duration_since(started),
+            _ => return Duration::ZERO,
+        };
+        match self.control {
+            TimeControl::Bronstein {delay, ..} => spent.saturating_sub(delay),
+            _ => spent,
+        }
+    }
+
+    pub fn remaining(&self, side: Side, now: Instant) -> Duration {
+        self.remaining[index(side)].saturating_sub(self.charged(side, now))
+    }
+
+    /// side whose time is over
+    pub fn flagged(&self, now: Instant) -> Option<Side> {
+        let (side, _) = self.running?;
+        match self.remaining(side, now).is_zero() {
+            true => Some(side),
+            false => None,
+        }
+    }
+
+    /// turn of the running side ends and `next` starts thinking
+    pub fn press(&mut self, next: Side, now: Instant) {
+        if let Some((side, _)) = self.running {
+            let i = index(side);
+            self.remaining[i] = self.remaining(side, now);
+            self.turns[i] += 1;
+            match self.control {
+                TimeControl::Fischer {increment, ..} => self.remaining[i] += increment,
+                TimeControl::MovesPerPeriod {moves, period} if self.turns[i].is_multiple_of(moves) => self.remaining[i] += period,
+                _ => (),
+            }
+        }
+        self.running = Some((next, now));
+    }
+
+    /// nobody thinks, like after the end of the game
+    pub fn stop(&mut self, now: Instant) {
+        if let Some((side, _)) = self.running {
+            self.remaining[index(side)] = self.remaining(side, now);
+        }
+        self.running = None;
+    }
+
+    /// time control, milliseconds left and finished turns of both sides, like "fischer 180 2/175300 180000 1 0"
+    pub fn format(&self, now: Instant) -> String {
+        let millis = |side| self.remaining(side, now).as_millis();
+        format!(
+            "{}/{} {} {} {}",
+            self.control, millis(Side::White), millis(Side::Black), self.turns[0], self.turns[1],
+        )
+    }
+
+    /// stopped clock from `format`, it starts with the next `press`
+    pub fn parse(s: &str) -> Option<Clock> {
+        let (control, state) = s.split_once('/')?;
+        let control = TimeControl::parse(control)?;
+        let numbers: Vec<u64> = state.split_whitespace().map(|word| word.parse().ok()).collect::<Option<_>>()?;
+        let &[white, black, white_turns, black_turns] = numbers.as_slice() else {
+            return None;
+        };
+        Some(Clock {
+            control,
+            remaining: [Duration::from_millis(white), Duration::from_millis(black)],
+            turns: [white_turns as usize, black_turns as usize],
+            running: None,
+        })
+    }
+
+    /// how long `side` may think on its turn, so the clock lasts for the whole game
+    pub fn think_time(&self, side: Side, now: Instant) -> Duration {
+        let remaining = self.remaining(side, now);
+        let budget = match self.control {
+            TimeControl::SuddenDeath {..} => remaining / EXPECTED_TURNS,
+            TimeControl::Fischer {increment, ..} => remaining / EXPECTED_TURNS + increment * 4 / 5,
+            TimeControl::Bronstein {delay, ..} => remaining / EXPECTED_TURNS + delay,
+            TimeControl::MovesPerPeriod {moves, ..} => {
+                let left = moves - self.turns[index(side)] % moves;
+                remaining / left as u32
+            },
+        };
+        // a turn must never take the clock down to zero
+        budget.min(remaining / 2).min(remaining.saturating_sub(SAFETY_MARGIN))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let controls = [
+            TimeControl::SuddenDeath {base: secs(300)},
+            TimeControl::Fischer {base: secs(180), increment: secs(2)},
+            TimeControl::Bronstein {base: secs(60), delay: secs(5)},
+            TimeControl::MovesPerPeriod {moves: 40, period: secs(600)},
+        ];
+        for control in controls {
+            assert_eq!(TimeControl::parse(&control.to_string()), Some(control));
+        }
+        assert_eq!(TimeControl::parse("fischer 300"), None);
+        assert_eq!(TimeControl::parse("moves-per-period 0 60"), None);
+        assert_eq!(format_time(Duration::from_millis(245_360)), "4:05.3");
+    }
+
+    #[test]
+    fn fischer_and_flag() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::Fischer {base: secs(10), increment: secs(2)});
+        clock.press(Side::White, start);
+        assert_eq!(clock.remaining(Side::White, start + secs(3)), secs(7));
+        clock.press(Side::Black, start + secs(3));
+        assert_eq!(clock.remaining(Side::White, start + secs(100)), secs(9));
+        assert_eq!(clock.flagged(start + secs(12)), None);
+        assert_eq!(clock.flagged(start + secs(13)), Some(Side::Black));
+
+        clock.stop(start + secs(4));
+        assert_eq!(clock.running_side(), None);
+        assert_eq!(clock.remaining(Side::Black, start + secs(100)), secs(9));
+    }
+
+    #[test]
+    fn bronstein_and_periods() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::Bronstein {base: secs(10), delay: secs(3)});
+        clock.press(Side::White, start);
+        assert_eq!(clock.remaining(Side::White, start + secs(2)), secs(10));
+        clock.press(Side::Black, start + secs(5));
+        assert_eq!(clock.remaining(Side::White, start + secs(5)), secs(8));
+
+        let mut clock = Clock::new(TimeControl::MovesPerPeriod {moves: 2, period: secs(10)});
+        clock.press(Side::White, start);
+        clock.press(Side::Black, start + secs(1));
+        clock.press(Side::White, start + secs(2));
+        assert_eq!(clock.remaining(Side::White, start + secs(2)), secs(9));
+        clock.press(Side::Black, start + secs(3));
+        assert_eq!(clock.remaining(Side::White, start + secs(3)), secs(18));
+    }
+
+    #[test]
+    fn saved_clock() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::MovesPerPeriod {moves: 2, period: secs(10)});
+        clock.press(Side::White, start);
+        clock.press(Side::Black, start + secs(1));
+        clock.press(Side::White, start + secs(2));
+        let saved = clock.format(start + Duration::from_millis(2500));
+        assert_eq!(saved, "moves-per-period 2 10/8500 9000 1 1");
+
+        let mut clock = Clock::parse(&saved).unwrap();
+        assert_eq!(clock.running_side(), None);
+        assert_eq!(clock.remaining(Side::White, start + secs(100)), Duration::from_millis(8500));
+        // the second turn of white ends the period
+        clock.press(Side::White, start);
+        clock.press(Side::Black, start + secs(1));
+        assert_eq!(clock.remaining(Side::White, start + secs(1)), Duration::from_millis(17500));
+
+        assert!(Clock::parse("fischer 180 2/1 2 3").is_none());
+        assert!(Clock::parse("fischer 180 2").is_none());
+    }
+
+    #[test]
+    fn think_time_leaves_time() {
+        let start = Instant::now();
+        let clock = Clock::new(TimeControl::SuddenDeath {base: secs(60)});
+        assert_eq!(clock.think_time(Side::White, start), secs(2));
+
+        let clock = Clock::new(TimeControl::Fischer {base: Duration::from_millis(100), increment: secs(5)});
+        assert_eq!(clock.think_time(Side::White, start), Duration::from_millis(50));
+
+        let mut clock = Clock::new(TimeControl::MovesPerPeriod {moves: 5, period: secs(50)});
+        clock.press(Side::White, start);
+        clock.press(Side::Black, start);
+        assert_eq!(clock.think_time(Side::White, start), Duration::from_secs_f32(12.5));
+    }
+}
@@ -0,0 +1,592 @@
+//! DamExchange Protocol for games against other draughts programs over tcp
+//!
+//! messages are ascii text ended with a zero byte, the first char is the type of the message.
+//! the board is 8x8, so squares are numbered 1-32 and a position has 32 cells
+//! instead of 50 of international draughts, the same way other 8x8 programs extend the protocol
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Instant;
+
+use crate::board::{Board, BoardBuilder, Cell, Move, Side, Square};
+use crate::cli::{self, ChouseMove};
+use crate::game::{Game, GameResult};
+use crate::notation;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:27531";
+const VERSION: u8 = 1;
+const NAME: &str = "checkers";
+
+const DXP_USAGE: &str = "usage: checkers dxp listen [address] [--opponent random|minmax5|minmax10|minmax15]
+       checkers dxp connect [address] [--opponent random|minmax5|minmax10|minmax15] [--side white|black] [--time minutes] [--moves amount]
+default address is 127.0.0.1:27531";
+
+#[derive(Debug)]
+pub enum DxpError {
+    Io(io::Error),
+    /// message which can't be read
+    BadMessage(String),
+    /// message which is fine but can't be sent now
+    Unexpected(Message),
+    IllegalMove(Message),
+    Declined(AcceptCode),
+}
+
+impl fmt::Display for DxpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DxpError::Io(e) => write!(f, "{}", e),
+            DxpError::BadMessage(s) => write!(f, "bad message \"{}\"", s),
+            DxpError::Unexpected(m) => write!(f, "unexpected message \"{}\"", m),
+            DxpError::IllegalMove(m) => write!(f, "illegal move \"{}\"", m),
+            DxpError::Declined(code) => write!(f, "game declined: {}", code),
+        }
+    }
+}
+
+impl Error for DxpError {}
+
+impl From<io::Error> for DxpError {
+    fn from(e: io::Error) -> Self {
+        DxpError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AcceptCode {
+    Accepted,
+    WrongSide,
+    WrongTime,
+    WrongPosition,
+    Other,
+}
+
+impl fmt::Display for AcceptCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AcceptCode::Accepted => write!(f, "accepted"),
+            AcceptCode::WrongSide => write!(f, "side is not accepted"),
+            AcceptCode::WrongTime => write!(f, "time is not accepted"),
+            AcceptCode::WrongPosition => write!(f, "position is not accepted"),
+            AcceptCode::Other => write!(f, "not accepted"),
+        }
+    }
+}
+
+/// result of the game from the side of the one who sends GAMEEND
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EndReason {
+    Unknown,
+    Lose,
+    Draw,
+    Win,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Chat(String),
+    GameReq {
+        name: String,
+        /// side of the one who accepts the game
+        follower_side: Side,
+        /// minutes for the game
+        time: u32,
+        /// amount of moves for the time
+        moves: u32,
+        /// side to move and 32 cells, None for the start position
+        position: Option<String>,
+    },
+    GameAcc {name: String, code: AcceptCode},
+    Move {
+        /// seconds spent on the move
+        time: u32,
+        from: Square,
+        to: Square,
+        captured: Vec<Square>,
+    },
+    GameEnd {reason: EndReason, stop: bool},
+    /// take back moves so `move_number` is played next by `side`
+    BackReq {move_number: u32, side: Side},
+    BackAcc(AcceptCode),
+}
+
+fn side_char(side: Side) -> char {
+    match side {
+        Side::White => 'W',
+        Side::Black => 'Z',
+    }
+}
+
+fn parse_side_char(c: char) -> Option<Side> {
+    match c {
+        'W' => Some(Side::White),
+        'Z' => Some(Side::Black),
+        _ => None,
+    }
+}
+
+fn format_position(board: &Board) -> String {
+    let mut result = String::new();
+    result.push(side_char(board.who_turn()));
+    for number in 1..=32 {
+        result.push(match board.get_cell(Square::from_number(number).unwrap()) {
+            Cell::White => 'w',
+            Cell::Black => 'z',
+            Cell::WhiteKing => 'W',
+            Cell::BlackKing => 'Z',
+            Cell::Empty => 'e',
+        });
+    }
+    result
+}
+
+fn parse_position(s: &str) -> Option<Board> {
+    let mut chars = s.chars();
+    let mut builder = BoardBuilder::new().side(parse_side_char(chars.next()?)?);
+    for number in 1..=32 {
+        let cell = match chars.next()? {
+            'w' => Cell::White,
+            'z' => Cell::Black,
+            'W' => Cell::WhiteKing,
+            'Z' => Cell::BlackKing,
+            'e' => Cell::Empty,
+            _ => return None,
+        };
+        if cell != Cell::Empty {
+            builder = builder.piece(Square::from_number(number).ok()?, cell);
+        }
+    }
+    builder.build().ok()
+}
+
+/// text of fixed width, like names padded to 32 chars
+fn field(s: &str, start: usize, len: usize) -> Option<&str> {
+    s.get(start..start + len)
+}
+
+fn number<T: std::str::FromStr>(s: &str, start: usize, len: usize) -> Option<T> {
+    field(s, start, len)?.parse().ok()
+}
+
+fn square(s: &str, start: usize) -> Option<Square> {
+    Square::from_number(number(s, start, 2)?).ok()
+}
+
+fn parse_game_req(body: &str) -> Option<Message> {
+    let position = match field(body, 41, 1)? {
+        "A" => None,
+        "B" => Some(field(body, 42, 33)?.to_string()),
+        _ => return None,
+    };
+    Some(Message::GameReq {
+        name: field(body, 2, 32)?.trim_end().to_string(),
+        follower_side: parse_side_char(field(body, 34, 1)?.chars().next()?)?,
+        time: number(body, 35, 3)?,
+        moves: number(body, 38, 3)?,
+        position,
+    })
+}
+
+fn parse_game_acc(body: &str) -> Option<Message> {
+    let code = match field(body, 32, 1)? {
+        "0" => AcceptCode::Accepted,
+        "1" => AcceptCode::WrongSide,
+        "2" => AcceptCode::WrongTime,
+        "3" => AcceptCode::WrongPosition,
+        _ => AcceptCode::Other,
+    };
+    Some(Message::GameAcc {name: field(body, 0, 32)?.trim_end().to_string(), code})
+}
+
+fn parse_move(body: &str) -> Option<Message> {
+    let amount: usize = number(body, 8, 2)?;
+    let mut captured = vec![];
+    for i in 0..amount {
+        captured.push(square(body, 10 + i * 2)?);
+    }
+    Some(Message::Move {time: number(body, 0, 4)?, from: square(body, 4)?, to: square(body, 6)?, captured})
+}
+
+fn parse_game_end(body: &str) -> Option<Message> {
+    let reason = match field(body, 0, 1)? {
+        "1" => EndReason::Lose,
+        "2" => EndReason::Draw,
+        "3" => EndReason::Win,
+        _ => EndReason::Unknown,
+    };
+    Some(Message::GameEnd {reason, stop: field(body, 1, 1)? == "1"})
+}
+
+fn parse_back_req(body: &str) -> Option<Message> {
+    Some(Message::BackReq {
+        move_number: number(body, 0, 3)?,
+        side: parse_side_char(field(body, 3, 1)?.chars().next()?)?,
+    })
+}
+
+impl Message {
+    pub fn parse(s: &str) -> Result<Message, DxpError> {
+        let body = s.get(1..).unwrap_or("");
+        let message = match s.chars().next() {
+            Some('C') => Some(Message::Chat(body.to_string())),
+            Some('R') => parse_game_req(body),
+            Some('A') => parse_game_acc(body),
+            Some('M') => parse_move(body),
+            Some('E') => parse_game_end(body),
+            Some('B') => parse_back_req(body),
+            Some('K') => match field(body, 0, 1) {
+                Some("0") => Some(Message::BackAcc(AcceptCode::Accepted)),
+                Some(_) => Some(Message::BackAcc(AcceptCode::Other)),
+                None => None,
+            },
+            _ => None,
+        };
+        message.ok_or_else(|| DxpError::BadMessage(s.to_string()))
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Chat(text) => write!(f, "C{}", text),
+            Message::GameReq {name, follower_side, time, moves, position} => {
+                write!(f, "R{:02}{:<32.32}{}{:03}{:03}", VERSION, name, side_char(*follower_side), time, moves)?;
+                match position {
+                    Some(position) => write!(f, "B{}", position),
+                    None => write!(f, "A"),
+                }
+            },
+            Message::GameAcc {name, code} => write!(f, "A{:<32.32}{}", name, match code {
+                AcceptCode::Accepted => 0,
+                AcceptCode::WrongSide => 1,
+                AcceptCode::WrongTime => 2,
+                AcceptCode::WrongPosition => 3,
+                AcceptCode::Other => 9,
+            }),
+            Message::Move {time, from, to, captured} => {
+                write!(f, "M{:04}{:02}{:02}{:02}", time, from.number(), to.number(), captured.len())?;
+                for square in captured {
+                    write!(f, "{:02}", square.number())?;
+                }
+                Ok(())
+            },
+            Message::GameEnd {reason, stop} => write!(f, "E{}{}", match reason {
+                EndReason::Unknown => 0,
+                EndReason::Lose => 1,
+                EndReason::Draw => 2,
+                EndReason::Win => 3,
+            }, if *stop { 1 } else { 0 }),
+            Message::BackReq {move_number, side} => write!(f, "B{:03}{}", move_number, side_char(*side)),
+            Message::BackAcc(code) => write!(f, "K{}", match code {
+                AcceptCode::Accepted => 0,
+                _ => 2,
+            }),
+        }
+    }
+}
+
+/// messages over a tcp stream
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Result<Self, DxpError> {
+        Ok(Connection {reader: BufReader::new(stream.try_clone()?), writer: stream})
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), DxpError> {
+        self.writer.write_all(message.to_string().as_bytes())?;
+        self.writer.write_all(&[0])?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// next message, None when the other side closed the connection
+    pub fn receive(&mut self) -> Result<Option<Message>, DxpError> {
+        let mut buf = vec![];
+        if self.reader.read_until(0, &mut buf)? == 0 {
+            return Ok(None);
+        }
+        if buf.last() == Some(&0) {
+            buf.pop();
+        }
+        Message::parse(&String::from_utf8_lossy(&buf)).map(Some)
+    }
+}
+
+/// turn from the message, if it is one of the available turns
+fn find_turn(board: &Board, from: Square, to: Square, captured: &[Square]) -> Option<Vec<Move>> {
+    let mut captured = captured.to_vec();
+    captured.sort_by_key(|square| square.number());
+    notation::complete_turns(board).into_iter().find(|mvs| {
+        let mut turn_captured = notation::captured_squares(board, mvs);
+        turn_captured.sort_by_key(|square| square.number());
+        mvs[0].from == from && mvs[mvs.len() - 1].to == to && turn_captured == captured
+    })
+}
+
+fn end_reason(result: GameResult, side: Side) -> EndReason {
+    match result {
+        GameResult::Win(winner) if winner == side => EndReason::Win,
+        GameResult::Win(_) => EndReason::Lose,
+        GameResult::Draw => EndReason::Draw,
+        GameResult::Ongoing => EndReason::Unknown,
+    }
+}
+
+pub fn result_text(result: GameResult) -> String {
+    match result {
+        GameResult::Win(side) => format!("{} won", side),
+        GameResult::Draw => "draw".to_string(),
+        GameResult::Ongoing => "game is not ended".to_string(),
+    }
+}
+
+/// go back to the turn `move_number` of `side`, false if there is no such turn
+fn take_back(game: &mut Game, move_number: u32, side: Side) -> bool {
+    let offset = match game.start_board().who_turn() {
+        Side::White => 0,
+        Side::Black => 1,
+    };
+    let index = (move_number as usize * 2).checked_sub(2 + offset).map(|i| match side {
+        Side::White => i,
+        Side::Black => i + 1,
+    });
+    let ply = game.ply();
+    let played: Vec<_> = game.turns().into_iter().filter(|turn| turn.first_ply < ply).collect();
+    match index {
+        Some(i) if i == played.len() => true,
+        Some(i) if i < played.len() => game.go_to_ply(played[i].first_ply),
+        _ => false,
+    }
+}
+
+/// play one game, `side` is the side of this program
+pub fn play_game(connection: &mut Connection, game: &mut Game, side: Side, chouse_func: ChouseMove) -> Result<GameResult, DxpError> {
+    loop {
+        if game.board().who_turn() == side && !game.board().is_ended() {
+            let before = *game.board();
+            let start = Instant::now();
+            let mvs = cli::play_turn(game, chouse_func).map_err(|e| DxpError::BadMessage(e.to_string()))?;
+            if mvs.is_empty() {
+                break;
+            }
+            println!("{} played {}", side, notation::format_turn(&before, &mvs));
+            connection.send(&Message::Move {
+                time: start.elapsed().as_secs() as u32,
+                from: mvs[0].from,
+                to: mvs[mvs.len() - 1].to,
+                captured: notation::captured_squares(&before, &mvs),
+            })?;
+            if game.board().is_ended() {
+                connection.send(&Message::GameEnd {reason: end_reason(game.result(), side), stop: false})?;
+                return wait_game_end(connection, game);
+            }
+            continue;
+        }
+
+        let message = match connection.receive()? {
+            Some(message) => message,
+            None => return Ok(game.result()),
+        };
+        match message {
+            Message::Chat(text) => println!("chat: {}", text),
+            Message::Move {from, to, ref captured, ..} if game.board().who_turn() != side && !game.board().is_ended() => {
+                let before = *game.board();
+                let mvs = match find_turn(&before, from, to, captured) {
+                    Some(mvs) => mvs,
+                    None => {
+                        connection.send(&Message::GameEnd {reason: EndReason::Unknown, stop: true})?;
+                        return Err(DxpError::IllegalMove(message));
+                    },
+                };
+                for &mv in mvs.iter() {
+                    game.do_move(mv).map_err(|_| DxpError::IllegalMove(message.clone()))?;
+                }
+                println!("{} played {}", !side, notation::format_turn(&before, &mvs));
+            },
+            Message::GameEnd {reason, stop} => {
+                let result = match reason {
+                    _ if game.board().is_ended() => game.result(),
+                    EndReason::Lose => GameResult::Win(side),
+                    EndReason::Win => GameResult::Win(!side),
+                    EndReason::Draw => GameResult::Draw,
+                    EndReason::Unknown => GameResult::Ongoing,
+                };
+                game.set_result(result);
+                connection.send(&Message::GameEnd {reason: end_reason(result, side), stop})?;
+                return Ok(result);
+            },
+            Message::BackReq {move_number, side: back_side} => {
+                let code = match take_back(game, move_number, back_side) {
+                    true => AcceptCode::Accepted,
+                    false => AcceptCode::Other,
+                };
+                connection.send(&Message::BackAcc(code))?;
+            },
+            message => return Err(DxpError::Unexpected(message)),
+        }
+    }
+    Ok(game.result())
+}
+
+/// after our GAMEEND the other side answers with its own
+fn wait_game_end(connection: &mut Connection, game: &mut Game) -> Result<GameResult, DxpError> {
+    loop {
+        match connection.receive()? {
+            Some(Message::GameEnd {..}) | None => return Ok(game.result()),
+            Some(Message::Chat(text)) => println!("chat: {}", text),
+            Some(message) => return Err(DxpError::Unexpected(message)),
+        }
+    }
+}
+
+/// wait for one connection and play games from it while they are requested
+pub fn listen(address: &str, chouse_func: ChouseMove) -> Result<(), DxpError> {
+    let listener = TcpListener::bind(address)?;
+    println!("listening on {}", listener.local_addr()?);
+    io::stdout().flush()?;
+    let (stream, _) = listener.accept()?;
+    let mut connection = Connection::new(stream)?;
+
+    loop {
+        let (side, position) = match connection.receive()? {
+            Some(Message::GameReq {follower_side, position, ..}) => (follower_side, position),
+            Some(Message::Chat(text)) => {
+                println!("chat: {}", text);
+                continue;
+            },
+            Some(message) => return Err(DxpError::Unexpected(message)),
+            None => return Ok(()),
+        };
+        let board = match position {
+            Some(position) => parse_position(&position),
+            None => Some(Board::new()),
+        };
+        let board = match board {
+            Some(board) => board,
+            None => {
+                connection.send(&Message::GameAcc {name: NAME.to_string(), code: AcceptCode::WrongPosition})?;
+                continue;
+            },
+        };
+        connection.send(&Message::GameAcc {name: NAME.to_string(), code: AcceptCode::Accepted})?;
+        let mut game = Game::from_board(board);
+        let result = play_game(&mut connection, &mut game, side, chouse_func)?;
+        println!("result: {}", result_text(result));
+    }
+}
+
+/// ask for a game from `start` position or from the usual one and play it
+pub fn connect(address: &str, side: Side, time: u32, moves: u32, start: Option<Board>, chouse_func: ChouseMove) -> Result<GameResult, DxpError> {
+    let mut connection = Connection::new(TcpStream::connect(address)?)?;
+    connection.send(&Message::GameReq {
+        name: NAME.to_string(),
+        follower_side: !side,
+        time,
+        moves,
+        position: start.as_ref().map(format_position),
+    })?;
+    match connection.receive()? {
+        Some(Message::GameAcc {code: AcceptCode::Accepted, ..}) => (),
+        Some(Message::GameAcc {code, ..}) => return Err(DxpError::Declined(code)),
+        Some(message) => return Err(DxpError::Unexpected(message)),
+        None => return Err(DxpError::Io(io::ErrorKind::UnexpectedEof.into())),
+    }
+    let mut game = Game::from_board(start.unwrap_or_else(Board::new));
+    let result = play_game(&mut connection, &mut game, side, chouse_func)?;
+    println!("result: {}", result_text(result));
+    Ok(result)
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", DXP_USAGE);
+        return Ok(());
+    }
+    let address = match args.get(1) {
+        Some(arg) if !arg.starts_with("--") => arg.as_str(),
+        _ => DEFAULT_ADDRESS,
+    };
+    let opponent_name = cli::option_value(args, "--opponent")?.unwrap_or("minmax5");
+    let chouse_func = match cli::parse_opponent(opponent_name)? {
+        Some(chouse_func) => chouse_func,
+        None => return Err("dxp needs a computer player".into()),
+    };
+
+    match args.first().map(|arg| arg.as_str()) {
+        Some("listen") => listen(address, chouse_func)?,
+        Some("connect") => {
+            let side = cli::parse_side(cli::option_value(args, "--side")?.unwrap_or("white"))?;
+            let time = cli::option_value(args, "--time")?.unwrap_or("5").parse()?;
+            let moves = cli::option_value(args, "--moves")?.unwrap_or("75").parse()?;
+            connect(address, side, time, moves, None, chouse_func)?;
+        },
+        _ => return Err(DXP_USAGE.into()),
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(number: usize) -> Square {
+        Square::from_number(number).unwrap()
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = vec![
+            Message::Chat("hello".to_string()),
+            Message::GameReq {name: "checkers".to_string(), follower_side: Side::Black, time: 5, moves: 75, position: None},
+            Message::GameReq {name: "checkers".to_string(), follower_side: Side::White, time: 10, moves: 50, position: Some(format_position(&Board::new()))},
+            Message::GameAcc {name: "other".to_string(), code: AcceptCode::Accepted},
+            Message::Move {time: 3, from: sq(22), to: sq(6), captured: vec![sq(18), sq(10)]},
+            Message::GameEnd {reason: EndReason::Win, stop: true},
+            Message::BackReq {move_number: 12, side: Side::Black},
+            Message::BackAcc(AcceptCode::Accepted),
+        ];
+        for message in messages {
+            let text = message.to_string();
+            assert_eq!(Message::parse(&text).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn message_format() {
+        let message = Message::Move {time: 12, from: sq(22), to: sq(6), captured: vec![sq(18), sq(10)]};
+        assert_eq!(message.to_string(), "M001222060218 10".replace(' ', ""));
+        let request = Message::GameReq {name: "x".to_string(), follower_side: Side::Black, time: 5, moves: 75, position: None};
+        assert_eq!(request.to_string(), format!("R01x{}Z005075A", " ".repeat(31)));
+        assert!(Message::parse("M12").is_err());
+        assert!(Message::parse("Q").is_err());
+    }
+
+    #[test]
+    fn position_with_black_to_move() {
+        let mut board = Board::new();
+        board.do_move(Move::new(sq(22), sq(18))).unwrap();
+        let parsed = parse_position(&format_position(&board)).unwrap();
+        assert_eq!(format_position(&parsed), format_position(&board));
+        assert_eq!(parsed.who_turn(), Side::Black);
+    }
+
+    #[test]
+    fn take_back_turns() {
+        let mut game = Game::new();
+        for mv in [(22, 18), (11, 15), (18, 11)] {
+            game.do_move(Move::new(sq(mv.0), sq(mv.1))).unwrap();
+        }
+        assert!(!take_back(&mut game, 5, Side::White));
+        assert!(take_back(&mut game, 2, Side::White));
+        assert_eq!(game.ply(), 2);
+        assert!(take_back(&mut game, 1, Side::Black));
+        assert_eq!(game.ply(), 1);
+    }
+}
@@ -0,0 +1,146 @@
+//! position setup in the window, pieces are put by clicks and checked before the game starts
+
+use crate::board::{Board, BoardBuilder, Cell, Side, Square};
+use crate::notation;
+
+pub struct Editor {
+    pub builder: BoardBuilder,
+    /// piece put by a click, `Cell::Empty` removes pieces
+    pub tool: Cell,
+    /// text field for import and export
+    pub fen: String,
+    pub error: Option<String>,
+}
+
+impl Editor {
+    /// setup of `board`, so it can be changed a bit
+    pub fn new(board: &Board) -> Self {
+        let mut editor = Editor {
+            builder: BoardBuilder::from_board(board),
+            tool: Cell::White,
+            fen: String::new(),
+            error: None,
+        };
+        editor.export_fen();
+        editor
+    }
+
+    /// put the tool piece, a click on the same piece removes it
+    pub fn click(&mut self, square: Square) {
+        let cell = match self.builder.get_cell(square) {
+            cell if cell == self.tool => Cell::Empty,
+            _ => self.tool,
+        };
+        self.builder = self.builder.piece(square, cell);
+        self.error = None;
+    }
+
+    pub fn remove(&mut self, square: Square) {
+        self.builder = self.builder.piece(square, Cell::Empty);
+        self.error = None;
+    }
+
+    pub fn set_side(&mut self, side: Side) {
+        self.builder = self.builder.side(side);
+        self.error = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.builder = BoardBuilder::new().side(self.builder.who_turn());
+        self.error = None;
+    }
+
+    pub fn start_position(&mut self) {
+        self.builder = BoardBuilder::from_board(&Board::new());
+        self.error = None;
+    }
+
+    /// position from the text field
+    pub fn import_fen(&mut self) {
+        match notation::parse_fen(self.fen.trim()) {
+            Ok(board) => {
+                self.builder = BoardBuilder::from_board(&board);
+                self.error = None;
+            },
+            Err(e) => self.error = Some(format!("Bad FEN: {}", e)),
+        }
+    }
+
+    /// position to the text field
+    pub fn export_fen(&mut self) {
+        match self.board() {
+            Ok(board) => self.fen = notation::format_fen(&board),
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// position a game can start from
+    pub fn board(&self) -> Result<Board, String> {
+        let board = self.builder.build().map_err(|e| e.to_string())?;
+        for side in [Side::White, Side::Black] {
+            let has_pieces = (1..=32).filter_map(|number| Square::from_number(number).ok()).any(|square| match board.get_cell(square) {
+                Cell::White | Cell::WhiteKing => side == Side::White,
+                Cell::Black | Cell::BlackKing => side == Side::Black,
+                Cell::Empty => false,
+            });
+            if !has_pieces {
+                return Err(format!("{} has no pieces", side));
+            }
+        }
+        if board.is_ended() {
+            return Err(format!("{} can't move", self.builder.who_turn()));
+        }
+        Ok(board)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(number: usize) -> Square {
+        Square::from_number(number).unwrap()
+    }
+
+    #[test]
+    fn put_and_remove_pieces() {
+        let mut editor = Editor::new(&Board::new());
+        assert_eq!(editor.fen, notation::format_fen(&Board::new()));
+        editor.clear();
+        editor.click(sq(22));
+        editor.tool = Cell::BlackKing;
+        editor.click(sq(5));
+        editor.click(sq(10));
+        editor.click(sq(10));
+        editor.set_side(Side::Black);
+        editor.export_fen();
+        assert_eq!(editor.error, None);
+        assert_eq!(editor.fen, "B:W22:BK5");
+
+        editor.remove(sq(22));
+        assert_eq!(editor.board().err(), Some("white has no pieces".to_string()));
+        editor.export_fen();
+        assert!(editor.error.is_some());
+    }
+
+    #[test]
+    fn validation() {
+        let mut editor = Editor::new(&Board::new());
+        editor.clear();
+        editor.click(sq(1));
+        editor.tool = Cell::Black;
+        editor.click(sq(30));
+        assert!(editor.board().is_err());
+
+        // white man on 29 is blocked by black men
+        editor.fen = "W:W29:B25,22".to_string();
+        editor.import_fen();
+        assert_eq!(editor.error, None);
+        assert_eq!(editor.board().err(), Some("white can't move".to_string()));
+
+        editor.fen = "W:W33".to_string();
+        editor.import_fen();
+        assert!(editor.error.as_deref().is_some_and(|e| e.starts_with("Bad FEN")));
+    }
+}
@@ -0,0 +1,396 @@
+use std::time::SystemTime;
+
+use crate::board::{Board, DrawReason, Move, MoveError, Side, State};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GameResult {
+    Ongoing,
+    Win(Side),
+    Draw,
+}
+
+/// one move of the game and position after it
+#[derive(Copy, Clone)]
+pub struct Ply {
+    pub mv: Move,
+    pub board: Board,
+    pub time: SystemTime,
+}
+
+/// all moves one side made before the other side's turn
+#[derive(Clone)]
+pub struct Turn {
+    pub side: Side,
+    /// position before the first move of the turn
+    pub board: Board,
+    pub mvs: Vec<Move>,
+    /// ply of the first move, `Game::go_to_ply(first_ply + mvs.len())` shows position after the turn
+    pub first_ply: usize,
+}
+
+#[derive(Clone)]
+struct Node {
+    ply: Option<Ply>,
+    parent: Option<usize>,
+    /// first child continues the line, others are variations
+    children: Vec<usize>,
+}
+
+/// board with the tree of moves which lead to it
+///
+/// every `Board::do_move` is one ply, so a multi-jump takes several plies
+#[derive(Clone)]
+pub struct Game {
+    start: Board,
+    nodes: Vec<Node>,
+    current: usize,
+    result: Option<GameResult>,
+    /// metadata like players names, event and date, same as pdn tags
+    pub tags: Vec<(String, String)>,
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Game::from_board(Board::new())
+    }
+
+    pub fn from_board(start: Board) -> Self {
+        Game {
+            start,
+            nodes: vec![Node {ply: None, parent: None, children: vec![]}],
+            current: 0,
+            result: None,
+            tags: vec![],
+        }
+    }
+
+    fn node_board(&self, node: usize) -> &Board {
+        match &self.nodes[node].ply {
+            Some(ply) => &ply.board,
+            None => &self.start,
+        }
+    }
+
+    /// current position
+    pub fn board(&self) -> &Board {
+        self.node_board(self.current)
+    }
+
+    pub fn start_board(&self) -> &Board {
+        &self.start
+    }
+
+    /// play a move from current position, if this move was already played it is reused
+    /// otherwise it becomes a new line or a variation
+    pub fn do_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        // an old line goes on after the position, but not after the end of the game
+        if self.result.is_some() || self.board().is_ended() {
+            return Err(MoveError::GameEnded);
+        }
+        for &child in self.nodes[self.current].children.iter() {
+            if self.nodes[child].ply.map(|ply| ply.mv) == Some(mv) {
+                self.current = child;
+                return Ok(());
+            }
+        }
+
+        let mut board = *self.board();
+        board.do_move(mv)?;
+        if self.times_played(&board) >= 2 {
+            board.draw(DrawReason::Repetition);
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            ply: Some(Ply {mv, board, time: SystemTime::now()}),
+            parent: Some(self.current),
+            children: vec![],
+        });
+        self.nodes[self.current].children.push(node);
+        self.current = node;
+        Ok(())
+    }
+
+    /// how many times `board`, which comes after the current position, was already met,
+    /// only positions since the last capture or man move are looked at
+    fn times_played(&self, board: &Board) -> usize {
+        let key = board.position_key();
+        let mut times = 0;
+        let mut node = Some(self.current);
+        for _ in 0..board.reversible_moves() {
+            let n = match node {
+                Some(n) => n,
+                None => break,
+            };
+            if self.node_board(n).position_key() == key {
+                times += 1;
+            }
+            node = self.nodes[n].parent;
+        }
+        times
+    }
+
+    /// nodes from the start to the current one and then along the first children
+    fn line_nodes(&self) -> Vec<usize> {
+        let mut nodes = vec![];
+        let mut node = Some(self.current);
+        while let Some(n) = node {
+            nodes.push(n);
+            node = self.nodes[n].parent;
+        }
+        nodes.reverse();
+
+        let mut node = self.current;
+        while let Some(&child) = self.nodes[node].children.first() {
+            nodes.push(child);
+            node = child;
+        }
+        nodes
+    }
+
+    /// plies from the start to the current position
+    pub fn history(&self) -> Vec<Ply> {
+        self.line().into_iter().take(self.ply()).collect()
+    }
+
+    /// plies of the current line, including ones after the current position
+    pub fn line(&self) -> Vec<Ply> {
+        self.line_nodes().iter().filter_map(|&n| self.nodes[n].ply).collect()
+    }
+
+    /// plies of the current line grouped by turns
+    pub fn turns(&self) -> Vec<Turn> {
+        let mut turns: Vec<Turn> = vec![];
+        let mut board = self.start;
+        for (i, ply) in self.line().into_iter().enumerate() {
+            let side = board.who_turn();
+            match turns.last_mut() {
+                Some(turn) if board.jumping_piece().is_some() => {
+                    turn.mvs.push(ply.mv);
+                },
+                _ => turns.push(Turn {side, board, mvs: vec![ply.mv], first_ply: i}),
+            }
+            board = ply.board;
+        }
+        turns
+    }
+
+    /// amount of plies played to get to the current position
+    pub fn ply(&self) -> usize {
+        let mut ply = 0;
+        let mut node = self.current;
+        while let Some(parent) = self.nodes[node].parent {
+            ply += 1;
+            node = parent;
+        }
+        ply
+    }
+
+    /// move along the current line, return false if there is no such ply
+    pub fn go_to_ply(&mut self, ply: usize) -> bool {
+        match self.line_nodes().get(ply) {
+            Some(&node) => {
+                self.current = node;
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.nodes[self.current].children.is_empty()
+    }
+
+    /// forget moves after the current position, like a taken back turn
+    pub fn truncate(&mut self) {
+        // nodes stay in the tree but can't be reached anymore
+        self.nodes[self.current].children.clear();
+    }
+
+    /// result of the game, set by `set_result` or taken from the last position of the current line
+    pub fn result(&self) -> GameResult {
+        if let Some(result) = self.result {
+            return result;
+        }
+
+        let nodes = self.line_nodes();
+        match self.node_board(nodes[nodes.len() - 1]).state() {
+            State::WhiteWin => GameResult::Win(Side::White),
+            State::BlackWin => GameResult::Win(Side::Black),
+            State::Draw(_) => GameResult::Draw,
+            _ => GameResult::Ongoing,
+        }
+    }
+
+    /// players agreed to a draw in the current position
+    pub fn agree_draw(&mut self) {
+        match &mut self.nodes[self.current].ply {
+            Some(ply) => ply.board.agree_draw(),
+            None => self.start.agree_draw(),
+        }
+    }
+
+    /// for results which can't be seen on the board, like resignation
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = Some(result);
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
+
+    /// replace the value of the tag or add it at the end
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Square;
+
+    fn mv(from: usize, to: usize) -> Move {
+        Move::new(Square::from_number(from).unwrap(), Square::from_number(to).unwrap())
+    }
+
+    #[test]
+    fn history_follows_moves() {
+        let mut game = Game::new();
+        assert_eq!(game.do_move(mv(22, 18)), Ok(()));
+        assert_eq!(game.do_move(mv(11, 15)), Ok(()));
+        assert_eq!(game.ply(), 2);
+        assert_eq!(game.history().iter().map(|ply| ply.mv).collect::<Vec<_>>(), vec![mv(22, 18), mv(11, 15)]);
+        assert_eq!(game.board().who_turn(), Side::White);
+    }
+
+    #[test]
+    fn illegal_move_is_not_recorded() {
+        let mut game = Game::new();
+        assert!(game.do_move(mv(11, 15)).is_err());
+        assert_eq!(game.ply(), 0);
+        assert_eq!(game.line().len(), 0);
+    }
+
+    #[test]
+    fn navigation() {
+        let mut game = Game::new();
+        game.do_move(mv(22, 18)).unwrap();
+        game.do_move(mv(11, 15)).unwrap();
+        assert!(game.go_to_ply(0));
+        assert_eq!(game.board().who_turn(), Side::White);
+        assert_eq!(game.line().len(), 2);
+        assert!(game.go_to_ply(1));
+        assert_eq!(game.ply(), 1);
+        assert_eq!(game.history().len(), 1);
+        assert!(!game.go_to_ply(3));
+        assert!(game.go_to_ply(2));
+        assert!(game.is_at_end());
+    }
+
+    #[test]
+    fn turns_group_jumps() {
+        let board = Board::from_arr(Side::White, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', 'b'],
+            [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        let mut game = Game::from_board(board);
+        game.do_move(mv(22, 15)).unwrap();
+        game.do_move(mv(15, 6)).unwrap();
+        game.do_move(mv(20, 24)).unwrap();
+
+        let turns = game.turns();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].side, Side::White);
+        assert_eq!(turns[0].mvs, vec![mv(22, 15), mv(15, 6)]);
+        assert_eq!(turns[1].side, Side::Black);
+        assert_eq!(turns[1].first_ply, 2);
+    }
+
+    #[test]
+    fn variations() {
+        let mut game = Game::new();
+        game.do_move(mv(22, 18)).unwrap();
+        game.do_move(mv(11, 15)).unwrap();
+        game.go_to_ply(1);
+        game.do_move(mv(12, 16)).unwrap();
+        // the line goes on with the variation
+        assert_eq!(game.line()[1].mv, mv(12, 16));
+        game.go_to_ply(1);
+        assert_eq!(game.line()[1].mv, mv(11, 15));
+
+        // played moves are reused
+        game.do_move(mv(11, 15)).unwrap();
+        game.go_to_ply(1);
+        game.do_move(mv(12, 16)).unwrap();
+        assert_eq!(game.history()[1].mv, mv(12, 16));
+        assert_eq!(game.nodes.len(), 4);
+    }
+
+    #[test]
+    fn truncate() {
+        let mut game = Game::new();
+        game.do_move(mv(22, 18)).unwrap();
+        game.do_move(mv(11, 15)).unwrap();
+        game.go_to_ply(1);
+        game.truncate();
+        assert!(game.is_at_end());
+        assert_eq!(game.line().len(), 1);
+        game.do_move(mv(12, 16)).unwrap();
+        assert_eq!(game.line().len(), 2);
+        assert_eq!(game.line()[1].mv, mv(12, 16));
+    }
+
+    #[test]
+    fn result() {
+        let mut game = Game::new();
+        assert_eq!(game.result(), GameResult::Ongoing);
+        game.set_result(GameResult::Win(Side::Black));
+        assert_eq!(game.result(), GameResult::Win(Side::Black));
+    }
+
+    #[test]
+    fn draw_by_threefold_repetition() {
+        let mut game = Game::from_board(crate::notation::parse_fen("W:WK29:BK1").unwrap());
+        for _ in 0..2 {
+            assert_eq!(game.result(), GameResult::Ongoing);
+            for (from, to) in [(29, 25), (1, 5), (25, 29), (5, 1)] {
+                assert_eq!(game.do_move(mv(from, to)), Ok(()));
+            }
+        }
+        assert_eq!(game.board().state(), State::Draw(DrawReason::Repetition));
+
+        // the start position came twice more in the first line, but only once in this one
+        game.go_to_ply(0);
+        for (from, to) in [(29, 25), (1, 6), (25, 29), (6, 1)] {
+            assert_eq!(game.do_move(mv(from, to)), Ok(()));
+        }
+        assert_eq!(game.result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn no_moves_after_end() {
+        let mut game = Game::new();
+        game.do_move(mv(22, 18)).unwrap();
+        game.go_to_ply(0);
+        game.agree_draw();
+        assert!(game.do_move(mv(22, 18)).is_err());
+
+        let mut game = Game::new();
+        game.do_move(mv(22, 18)).unwrap();
+        game.go_to_ply(0);
+        game.set_result(GameResult::Win(Side::Black));
+        assert!(game.do_move(mv(22, 18)).is_err());
+        assert!(game.do_move(mv(21, 17)).is_err());
+    }
+}
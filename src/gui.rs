@@ -1,6 +1,19 @@
+use std::fs;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::ai;
 use crate::board;
+use crate::board_view;
+use crate::cli;
+use crate::clock;
+use crate::editor;
+use crate::game;
+use crate::notation;
+use crate::network;
+use crate::pdn;
+use crate::review;
+use crate::theme;
 use crate::player_random;
-use crate::player_minmax;
 
 #[derive(PartialEq, Copy, Clone)]
 enum GameMode {
@@ -10,6 +23,78 @@ enum GameMode {
     MinMax10,
     MinMax15,
     RandomVsRandom,
+    /// opponent plays in another window, see `network`
+    Network,
+    /// both sides are played by the user while the engine searches the shown position
+    Analysis,
+}
+
+impl GameMode {
+    /// name for the storage
+    fn name(self) -> &'static str {
+        match self {
+            GameMode::SelfPlay => "self_play",
+            GameMode::Random => "random",
+            GameMode::MinMax5 => "minmax5",
+            GameMode::MinMax10 => "minmax10",
+            GameMode::MinMax15 => "minmax15",
+            GameMode::RandomVsRandom => "random_vs_random",
+            GameMode::Network => "network",
+            GameMode::Analysis => "analysis",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "self_play" => Some(GameMode::SelfPlay),
+            "random" => Some(GameMode::Random),
+            "minmax5" => Some(GameMode::MinMax5),
+            "minmax10" => Some(GameMode::MinMax10),
+            "minmax15" => Some(GameMode::MinMax15),
+            "random_vs_random" => Some(GameMode::RandomVsRandom),
+            "network" => Some(GameMode::Network),
+            "analysis" => Some(GameMode::Analysis),
+            _ => None,
+        }
+    }
+}
+
+/// squares like "22 18"
+fn format_squares(squares: &[board::Square]) -> String {
+    squares.iter().map(|square| square.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// time between the previous turn and the last move of `turn`, the first turn has nothing to count from
+fn turn_time(line: &[game::Ply], turn: &game::Turn) -> Option<Duration> {
+    let previous = line.get(turn.first_ply.checked_sub(1)?)?;
+    let last = line.get(turn.first_ply + turn.mvs.len() - 1)?;
+    last.time.duration_since(previous.time).ok()
+}
+
+fn parse_squares(s: &str) -> Option<Vec<board::Square>> {
+    s.split_whitespace().map(|number| notation::parse_square(number).ok()).collect()
+}
+
+/// best moves shown in the analysis mode
+const ANALYSIS_LINES: usize = 3;
+
+/// background search of the shown position in the analysis mode
+struct Analysis {
+    /// position and the jumping piece, the search starts again when they change
+    key: String,
+    board: board::Board,
+    analyzer: ai::Analyzer,
+}
+
+/// depth of the search for hints, deeper than the computer player of the beginners
+const HINT_DEPTH: usize = 8;
+
+/// suggested turn for the player
+struct Hint {
+    /// moves of the game when the hint was asked, it is outdated after any move
+    moves: Vec<board::Move>,
+    thinker: Option<ai::Thinker>,
+    turn: Vec<board::Move>,
 }
 
 
@@ -17,11 +102,43 @@ pub struct App {
     show_game_ended_popup: bool,
     player_side: board::Side,
     game_mode: GameMode,
-    bd: board::Board,
-    highlighted: Vec<board::Point>,
-    selected_cell: Option<board::Point>,
-    moved_from: Vec<board::Point>,
-    moved_to: Option<board::Point>,
+    game: game::Game,
+    highlighted: Vec<board::Square>,
+    selected_cell: Option<board::Square>,
+    moved_from: Vec<board::Square>,
+    moved_to: Option<board::Square>,
+    move_error: Option<board::MoveError>,
+    network: Option<network::Peer>,
+    network_address: String,
+    /// news like declined draw offers, network or file errors
+    status_message: Option<String>,
+    /// opponent offered a draw and waits for the answer
+    draw_offered: bool,
+    /// computer searches its turn
+    thinker: Option<ai::Thinker>,
+    /// piece which follows the pointer
+    dragged: Option<board::Square>,
+    animation: Option<board_view::Animation>,
+    /// moves of the position which is painted or animated
+    shown_moves: Vec<board::Move>,
+    /// position is set up instead of played
+    editor: Option<editor::Editor>,
+    /// clock of the next games, no clock if None
+    time_control: Option<clock::TimeControl>,
+    clock: Option<clock::Clock>,
+    hint: Option<Hint>,
+    /// mark pieces which can be taken on the next turn
+    show_threats: bool,
+    analysis: Option<Analysis>,
+    /// engine checks the turns of the game, shown in its own window
+    review: Option<ai::Reviewer>,
+    theme: theme::Theme,
+    /// board coordinates of the square which the keyboard acts on
+    cursor: (usize, usize),
+    /// cursor moved by the keyboard, its square takes the focus on the next frame
+    focus_cursor: bool,
+    /// move typed like "22-18"
+    typed_move: String,
 }
 
 
@@ -31,162 +148,439 @@ impl App {
             show_game_ended_popup: false,
             player_side: board::Side::White,
             game_mode: GameMode::SelfPlay,
-            bd: board::Board::new(),
+            game: game::Game::new(),
             highlighted: vec![],
             moved_from: vec![],
             selected_cell: None,
             moved_to: None,
+            move_error: None,
+            network: None,
+            network_address: network::DEFAULT_ADDRESS.to_string(),
+            status_message: None,
+            draw_offered: false,
+            thinker: None,
+            dragged: None,
+            animation: None,
+            shown_moves: vec![],
+            editor: None,
+            time_control: None,
+            clock: None,
+            hint: None,
+            show_threats: false,
+            analysis: None,
+            review: None,
+            theme: theme::Theme::default(),
+            cursor: (2, 5),
+            focus_cursor: false,
+            typed_move: String::new(),
         };
         bd.highlight_available_checkers_to_move();
-        return bd;
+        bd
+    }
+
+    /// app with settings and the unfinished game from the last run
+    pub fn resumed(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut app = App::new();
+        if let Some(storage) = storage {
+            app.restore(storage);
+        }
+        app
+    }
+
+    fn restore(&mut self, storage: &dyn eframe::Storage) {
+        match storage.get_string("game_mode").as_deref().and_then(GameMode::from_name) {
+            // network game can't go on without the opponent
+            Some(GameMode::Network) | None => (),
+            Some(game_mode) => self.game_mode = game_mode,
+        }
+        if let Some(side) = storage.get_string("player_side").and_then(|side| cli::parse_side(&side).ok()) {
+            self.player_side = side;
+        }
+        if let Some(address) = storage.get_string("network_address") {
+            self.network_address = address;
+        }
+        if let Some(time_control) = storage.get_string("time_control") {
+            self.time_control = clock::TimeControl::parse(&time_control);
+        }
+        self.show_threats = storage.get_string("show_threats").as_deref() == Some("true");
+        self.restore_theme(storage);
+
+        // games from a set up position keep it, older settings have no start
+        let start = match storage.get_string("start").filter(|fen| !fen.is_empty()) {
+            Some(fen) => match notation::parse_fen(&fen) {
+                Ok(board) => board,
+                Err(_) => return,
+            },
+            None => board::Board::new(),
+        };
+        let mut game = game::Game::from_board(start);
+        let moves = storage.get_string("game").unwrap_or_default();
+        for text in moves.split_whitespace() {
+            let mv = match notation::parse_move(text).as_deref() {
+                Ok([mv]) => *mv,
+                _ => return,
+            };
+            if game.do_move(mv).is_err() {
+                return;
+            }
+        }
+        self.game = game;
+        // the clock goes on with the time which was left, a timed game can't start again with a full clock
+        self.clock = match storage.get_string("clock").filter(|clock| !clock.is_empty()) {
+            Some(clock) => clock::Clock::parse(&clock),
+            None if self.game.line().is_empty() => self.new_clock(),
+            None => None,
+        };
+        if self.computer_plays() && self.game.board().who_turn() != self.player_side {
+            self.enemy_try_move();
+            return self.highlight_available_checkers_to_move();
+        }
+        self.moved_from = storage.get_string("moved_from").and_then(|s| parse_squares(&s)).unwrap_or_default();
+        self.moved_to = storage.get_string("moved_to").and_then(|s| parse_squares(&s)).and_then(|squares| squares.first().copied());
+        self.highlight_available_checkers_to_move();
+    }
+
+    /// unknown names keep the default looks
+    fn restore_theme(&mut self, storage: &dyn eframe::Storage) {
+        let theme = &mut self.theme;
+        if let Some(board) = storage.get_string("board_style").as_deref().and_then(theme::BoardStyle::from_name) {
+            theme.board = board;
+        }
+        if let Some(pieces) = storage.get_string("piece_set").as_deref().and_then(theme::PieceSet::from_name) {
+            theme.pieces = pieces;
+        }
+        if let Some(coordinates) = storage.get_string("coordinates").as_deref().and_then(theme::Coordinates::from_name) {
+            theme.coordinates = coordinates;
+        }
+        if let Some(palette) = storage.get_string("palette").as_deref().and_then(theme::Palette::from_name) {
+            theme.palette = palette;
+        }
+        theme.light = storage.get_string("light_mode").as_deref() == Some("true");
+        theme.piece_dir = storage.get_string("piece_dir").filter(|path| !path.is_empty()).map(|path| theme::PieceDir::open(path.into()));
+    }
+
+    /// app which waits for the opponent on `address`
+    pub fn host(address: &str) -> Self {
+        let mut app = App::new();
+        app.network_address = address.to_string();
+        app.start_network(network::Role::Host);
+        app
+    }
+
+    /// app which plays the game hosted on `address`
+    pub fn join(address: &str) -> Self {
+        let mut app = App::new();
+        app.network_address = address.to_string();
+        app.start_network(network::Role::Guest);
+        app
+    }
+
+    fn is_guest(&self) -> bool {
+        self.network.as_ref().map(|peer| peer.role) == Some(network::Role::Guest)
     }
 
     fn restart(&mut self) {
+        // the game of the guest is set by the host
+        if self.is_guest() {
+            return;
+        }
+        self.thinker = None;
+        self.review = None;
         self.show_game_ended_popup = false;
-        self.bd = board::Board::new();
+        self.game = game::Game::new();
         self.selected_cell = None;
+        self.moved_from = vec![];
+        self.moved_to = None;
+        self.move_error = None;
+        self.draw_offered = false;
+        self.clock = self.new_clock();
         self.enemy_try_move();
         self.highlight_available_checkers_to_move();
+        self.send_sync();
     }
 
-    fn render_coordinate(&self, layout: &egui::Layout, ui: &mut egui::Ui, text: String) {
-        ui.with_layout(*layout, |ui| {
-            ui.label(text);
+    fn start_network(&mut self, role: network::Role) {
+        self.network = None;
+        self.status_message = None;
+        self.game_mode = GameMode::Network;
+        self.restart();
+        self.network = Some(match role {
+            network::Role::Host => network::Peer::host(&self.network_address),
+            network::Role::Guest => network::Peer::join(&self.network_address),
         });
+    }
 
+    fn send(&self, message: network::Message) {
+        if let Some(peer) = &self.network {
+            peer.send(message);
+        }
     }
 
-    fn render_cell(&mut self, ui: &mut egui::Ui, x: usize, y: usize) {
-        let color: egui::Color32;
-        let mut btn_text: Option<egui::WidgetText> = None;
-        if self.highlighted.contains(&board::Point::new(x, y)) {
-            color = egui::Color32::DARK_GREEN;
-        } else if self.moved_to.is_some() && self.moved_to.unwrap() == board::Point::new(x, y) {
-            color = egui::Color32::DARK_RED;
-        } else {
-            if self.bd.is_playable_cell(x, y) {
-                color = egui::Color32::BLACK;
-            } else {
-                color = egui::Color32::DARK_GRAY;
-            }
+    /// host tells the guest the whole game
+    fn send_sync(&self) {
+        if self.is_guest() {
+            return;
         }
+        self.send(network::Message::sync(&self.game, !self.player_side));
+    }
 
-        if let Some(index) = self.moved_from.iter().position(|&r| r == board::Point::new(x, y)) {
-            btn_text = Some(format!("{}", index).into());
+    fn poll_network(&mut self) {
+        loop {
+            let event = match self.network.as_mut().and_then(|peer| peer.poll()) {
+                Some(event) => event,
+                None => return,
+            };
+            match event {
+                network::Event::Connected => {
+                    self.status_message = None;
+                    self.send_sync();
+                },
+                network::Event::Disconnected => self.draw_offered = false,
+                network::Event::Message(message) => self.on_network_message(message),
+                network::Event::BadMessage(text) => self.status_message = Some(format!("Unknown message: {}", text)),
+                network::Event::Failed(e) => self.status_message = Some(format!("Network error: {}", e)),
+            }
         }
+    }
 
-        ui.with_layout(egui::Layout::centered_and_justified(egui::Direction::TopDown), |ui| {
-            ui.scope(|ui| {
-                ui.style_mut().visuals.widgets.inactive.weak_bg_fill = color;
+    /// the opponent doesn't agree with our game, the host's game wins
+    fn desync(&mut self, text: String) {
+        self.status_message = Some(text);
+        match self.is_guest() {
+            true => self.send(network::Message::Resync),
+            false => self.send_sync(),
+        }
+    }
 
-                let image = match self.bd.get_cell(x, y) {
-                    board::Cell::Empty => None,
-                    board::Cell::Black => Some(egui::Image::from_uri("file://assets/black_checker.png")),
-                    board::Cell::BlackKing => Some(egui::Image::from_uri("file://assets/black_king.png")),
-                    board::Cell::White => Some(egui::Image::from_uri("file://assets/white_checker.png")),
-                    board::Cell::WhiteKing => Some(egui::Image::from_uri("file://assets/white_king.png")),
+    fn on_network_message(&mut self, message: network::Message) {
+        // the game goes on from its last position
+        if self.is_reviewing() {
+            self.go_to_ply(self.game.line().len());
+        }
+        match message {
+            network::Message::Sync {side, result, moves} => {
+                if !self.is_guest() {
+                    return;
+                }
+                self.game = match network::synced_game(result, &moves) {
+                    Ok(game) => game,
+                    Err(e) => {
+                        self.status_message = Some(format!("Host sent an unavailable move: {}", e));
+                        return;
+                    },
                 };
-
-                if ui.add(egui::Button::opt_image_and_text(image, btn_text)).clicked() {
-                    self.on_click(x, y);
+                self.player_side = side;
+                self.show_game_ended_popup = false;
+                self.selected_cell = None;
+                self.moved_from = vec![];
+                self.moved_to = None;
+                self.move_error = None;
+                self.draw_offered = false;
+                self.highlight_available_checkers_to_move();
+            },
+            network::Message::Resync => self.send_sync(),
+            network::Message::Move(mv) => {
+                if self.game.board().who_turn() == self.player_side || self.game.result() != game::GameResult::Ongoing {
+                    return self.desync(format!("Opponent moved out of turn: {}-{}", mv.from, mv.to));
                 }
-            });
-        });
+                let turn_starts = self.game.board().jumping_piece().is_none();
+                if let Err(e) = self.game.do_move(mv) {
+                    return self.desync(format!("Opponent made an unavailable move: {}", e));
+                }
+                if turn_starts {
+                    self.moved_from = vec![];
+                }
+                self.moved_from.push(mv.from);
+                self.moved_to = Some(mv.to);
+                self.selected_cell = None;
+                self.highlight_available_checkers_to_move();
+            },
+            network::Message::Resign => {
+                self.game.set_result(game::GameResult::Win(self.player_side));
+                self.status_message = Some("Opponent resigned".to_string());
+            },
+            network::Message::DrawOffer => self.draw_offered = true,
+            network::Message::DrawAccept => self.game.agree_draw(),
+            network::Message::DrawDecline => self.status_message = Some("Opponent declined the draw".to_string()),
+        }
+    }
+
+    fn network_status(&self) -> Option<String> {
+        let peer = self.network.as_ref()?;
+        Some(match (peer.role, peer.connected) {
+            (_, true) => format!("Connected to {}, you play {}", peer.address, self.player_side),
+            (network::Role::Host, false) => format!("Waiting for the opponent on {}", peer.address),
+            (network::Role::Guest, false) => format!("Connecting to {}", peer.address),
+        })
+    }
+
+    fn can_play_network_game(&self) -> bool {
+        match &self.network {
+            Some(peer) => peer.connected && self.game.result() == game::GameResult::Ongoing,
+            None => false,
+        }
     }
 
     fn highlight_available_checkers_to_move(&mut self) {
-        let available_moves = self.bd.all_available_moves();
+        if self.thinker.is_some() {
+            self.highlighted = vec![];
+            return;
+        }
+        let available_moves = self.game.board().clone().all_available_moves();
         self.highlighted = vec![];
-        for i in 0..available_moves.len() {
-            self.highlighted.push(available_moves[i].from)
+        for mv in available_moves {
+            self.highlighted.push(mv.from)
         }
     }
 
     fn enemy_try_move(&mut self) {
         match self.game_mode {
-            GameMode::SelfPlay => (),
+            GameMode::SelfPlay | GameMode::Network | GameMode::Analysis => (),
             GameMode::RandomVsRandom => {
-                while !self.bd.is_ended() {
-                    let chouse_result = player_random::chouse_move(&mut self.bd);
-                    match chouse_result {
-                        Some(mv) => self.bd.do_move(mv).unwrap(),
-                        None => (),
-                    };
+                while !self.game.board().is_ended() {
+                    let chouse_result = player_random::chouse_move(&mut self.game.board().clone());
+                    if let Some(mv) = chouse_result {
+                        if let Err(e) = self.game.do_move(mv) {
+                            self.move_error = Some(e);
+                            break;
+                        }
+                    }
                 }
             },
             gm => {
-                let chouse_func = match gm {
-                    GameMode::Random => player_random::chouse_move,
-                    GameMode::MinMax5 => player_minmax::chouse_move5,
-                    GameMode::MinMax10 => player_minmax::chouse_move10,
-                    GameMode::MinMax15 => player_minmax::chouse_move15,
+                let player = match gm {
+                    GameMode::Random => ai::Player::Random,
+                    GameMode::MinMax5 => ai::Player::MinMax(5),
+                    GameMode::MinMax10 => ai::Player::MinMax(10),
+                    GameMode::MinMax15 => ai::Player::MinMax(15),
                     _ => unreachable!(),
                 };
 
-                self.moved_from = vec![];
-                while self.player_side != self.bd.who_turn() && !self.bd.is_ended() {
-                    let chouse_result = chouse_func(&mut self.bd);
-                    match chouse_result {
-                        Some(mv) => {
-                            self.bd.do_move(mv).unwrap();
-                            self.moved_from.push(mv.from);
-                            self.moved_to = Some(mv.to);
-                        },
-                        None => (),
-                    };
+                if self.player_side != self.game.board().who_turn() && !self.game.board().is_ended() && !self.is_reviewing() {
+                    let time = self.clock.as_ref().map(|clock| clock.think_time(self.game.board().who_turn(), Instant::now()));
+                    self.thinker = Some(ai::Thinker::start(player, *self.game.board(), time));
                 }
             },
         }
     }
 
+    /// play the turn of the computer if it is found
+    fn poll_thinker(&mut self) {
+        let mvs = match self.thinker.as_mut().and_then(|thinker| thinker.poll()) {
+            Some(mvs) => mvs,
+            None => return,
+        };
+        self.thinker = None;
+        // the turn goes to the end of the game, even if an earlier position is shown
+        let review_ply = match self.is_reviewing() {
+            true => Some(self.game.ply()),
+            false => None,
+        };
+        self.game.go_to_ply(self.game.line().len());
+        self.moved_from = vec![];
+        for mv in mvs {
+            if let Err(e) = self.game.do_move(mv) {
+                self.move_error = Some(e);
+                break;
+            }
+            self.moved_from.push(mv.from);
+            self.moved_to = Some(mv.to);
+        }
+        match review_ply {
+            Some(ply) => self.go_to_ply(ply),
+            None => self.highlight_available_checkers_to_move(),
+        }
+    }
+
+    /// ply before the last turn of the player, None if the computer moves first and there is nothing to take back
+    fn cancel_ply(&self) -> Option<usize> {
+        self.thinker.as_ref()?;
+        let last = self.game.line().len();
+        self.turn_ends().into_iter().rev().find(|&end| end < last)
+    }
+
+    /// stop the computer and take back the last turn of the player, so another one can be played
+    fn cancel_thinking(&mut self) {
+        let end = match self.cancel_ply() {
+            Some(end) => end,
+            None => return,
+        };
+        self.thinker = None;
+        self.go_to_ply(end);
+        self.game.truncate();
+        self.highlight_available_checkers_to_move();
+    }
+
+    fn thinking_status(&self) -> Option<String> {
+        let thinker = self.thinker.as_ref()?;
+        let seconds = thinker.started.elapsed().as_secs_f32();
+        Some(match thinker.info {
+            Some(info) => format!("Thinking… depth {}, {} nodes, {:.1}s", info.depth, info.nodes, seconds),
+            None => format!("Thinking… {:.1}s", seconds),
+        })
+    }
+
     fn show_game_ended_popup_if_game_ended(&mut self) {
-        if self.bd.is_ended() {
+        if self.is_reviewing() || self.editor.is_some() || self.review.is_some() {
+            return;
+        }
+        if self.game.board().is_ended() || self.game.result() != game::GameResult::Ongoing {
             self.show_game_ended_popup = true;
         }
     }
 
     /// return true if move success else - return false
-    fn try_move(&mut self, x: usize, y: usize) -> bool {
+    fn try_move(&mut self, square: board::Square) -> bool {
         match self.selected_cell {
             None => false,
             Some(selected_cell) => {
-                let destination_point = board::Point::new(x, y);
-                for i in 0..self.highlighted.len() {
-                    let highlighted_cell = self.highlighted[i];
-                    if highlighted_cell != destination_point {
-                        continue;
-                    }
-
-                    self.bd.do_move(board::Move::new(selected_cell.x, selected_cell.y, x, y)).unwrap();
-                    self.enemy_try_move();
-                    self.highlight_available_checkers_to_move();
-                    return true;
+                if self.game_mode == GameMode::Network && (!self.can_play_network_game() || self.game.board().who_turn() != self.player_side) {
+                    return false;
+                }
+                let mv = board::Move::new(selected_cell, square);
+                match self.game.do_move(mv) {
+                    Ok(()) => {
+                        self.send(network::Message::Move(mv));
+                        self.move_error = None;
+                        self.selected_cell = None;
+                        self.enemy_try_move();
+                        self.highlight_available_checkers_to_move();
+                        true
+                    },
+                    Err(e) => {
+                        self.move_error = Some(e);
+                        false
+                    },
                 }
-                return false;
             },
         }
     }
 
-    fn on_click(&mut self, x: usize, y: usize) {
-        match self.bd.get_cell(x, y) {
+    fn on_click(&mut self, square: board::Square) {
+        // earlier positions are only shown, the computer's turn can't be played
+        if self.is_reviewing() || self.thinker.is_some() {
+            return;
+        }
+        match self.game.board().get_cell(square) {
             board::Cell::Empty => {
-                if !self.try_move(x, y) {
+                if !self.try_move(square) {
+                    self.selected_cell = None;
                     self.highlight_available_checkers_to_move();
                 }
             },
             _ => {
-                let available_moves = self.bd.available_moves_for_cell(x, y);
+                let available_moves = self.game.board().available_moves_for_cell(square);
 
                 match available_moves.len() {
                     0 => {
                         self.highlight_available_checkers_to_move();
                     },
                     _ => {
-                        self.selected_cell = Some(board::Point::new(x, y));
+                        self.selected_cell = Some(square);
                         self.highlighted = vec![];
 
-                        for i in 0..available_moves.len() {
-                            self.highlighted.push(available_moves[i].to)
+                        for mv in available_moves {
+                            self.highlighted.push(mv.to)
                         }
                     },
                 }
@@ -194,131 +588,1708 @@ impl App {
         }
     }
 
-    fn render_board(&mut self, ui: &mut egui::Ui) {
-        let layout = egui::Layout::centered_and_justified(egui::Direction::TopDown);
-        let available_size = f32::min(ui.available_width(), ui.available_height());
-        egui::Grid::new("checkers_grid")
-        .spacing(egui::vec2(2., 2.))
-        .min_col_width(available_size / 9. -2.)
-        .max_col_width(available_size / 9. -2.)
-        .min_row_height(available_size / 9. -2.)
-        .show(ui, |ui| {
-            self.render_coordinate(&layout, ui, format!("{}", self.bd.move_amount));
-
-            let (start, end, step) = match self.player_side {
-                board::Side::White => (0, 8, 1),
-                board::Side::Black => (7, -1, -1),
-            };
-            let mut y = start;
-            let mut x = start;
+    /// arrows move the cursor on the screen, Enter and Space act like a click on its square
+    fn on_key(&mut self, key: egui::Key) {
+        let (dx, dy) = match key {
+            egui::Key::ArrowLeft => (-1, 0),
+            egui::Key::ArrowRight => (1, 0),
+            egui::Key::ArrowUp => (0, -1),
+            egui::Key::ArrowDown => (0, 1),
+            egui::Key::Enter | egui::Key::Space => {
+                let square = match board::Square::new(self.cursor.0, self.cursor.1) {
+                    Ok(square) => square,
+                    Err(_) => return,
+                };
+                match &mut self.editor {
+                    Some(editor) => editor.click(square),
+                    None => self.on_click(square),
+                }
+                return;
+            },
+            egui::Key::Delete | egui::Key::Backspace => {
+                if let (Some(editor), Ok(square)) = (&mut self.editor, board::Square::new(self.cursor.0, self.cursor.1)) {
+                    editor.remove(square);
+                }
+                return;
+            },
+            egui::Key::Escape => {
+                self.selected_cell = None;
+                self.highlight_available_checkers_to_move();
+                return;
+            },
+            _ => return,
+        };
+        self.cursor = board_view::step(self.cursor, dx, dy, self.player_side == board::Side::Black);
+        self.focus_cursor = true;
+    }
 
-            while x != end {
-                self.render_coordinate(&layout, ui, format!("{}", x));
-                x += step;
+    /// name of the square for screen readers, like "22 c3, white man, can move"
+    fn describe_square(&self, x: usize, y: usize) -> String {
+        let coordinates = format!("{}{}", theme::Coordinates::Algebraic.column(x), theme::Coordinates::Algebraic.row(y));
+        let square = match board::Square::new(x, y) {
+            Ok(square) => square,
+            Err(_) => return format!("{}, light square", coordinates),
+        };
+        let cell = match &self.editor {
+            Some(editor) => editor.builder.get_cell(square),
+            None => self.game.board().get_cell(square),
+        };
+        let piece = match cell {
+            board::Cell::Empty => "empty",
+            board::Cell::White => "white man",
+            board::Cell::WhiteKing => "white king",
+            board::Cell::Black => "black man",
+            board::Cell::BlackKing => "black king",
+        };
+        let mut text = format!("{} {}, {}", square, coordinates, piece);
+        if self.editor.is_none() {
+            if self.selected_cell == Some(square) {
+                text.push_str(", selected");
             }
-            ui.end_row();
-
-            while y != end {
-                x = start;
-                while x != end {
-                    if x == start {
-                        self.render_coordinate(&layout, ui, format!("{}", y));
-                    }
+            if self.highlighted.contains(&square) {
+                text.push_str(if cell == board::Cell::Empty { ", can move here" } else { ", can move" });
+            }
+            if self.moved_to == Some(square) || self.moved_from.contains(&square) {
+                text.push_str(", last move");
+            }
+        }
+        text
+    }
 
-                    self.render_cell(ui, x as usize, y as usize);
-                    x += step;
+    /// squares are widgets for the keyboard and screen readers, only the cursor square takes the focus
+    fn render_square_widgets(&mut self, ui: &mut egui::Ui, geometry: &board_view::Geometry) {
+        let board_id = ui.id().with("board_square");
+        let mut keys = vec![];
+        for y in 0..8 {
+            for x in 0..8 {
+                let id = board_id.with((x, y));
+                let rect = geometry.rect_at(x, y);
+                let is_cursor = (x, y) == self.cursor;
+                let sense = if is_cursor { egui::Sense::focusable_noninteractive() } else { egui::Sense::hover() };
+                let response = ui.interact(rect, id, sense);
+                let label = self.describe_square(x, y);
+                response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, &label));
+                if !is_cursor {
+                    continue;
                 }
-                ui.end_row();
-                y += step;
+                if std::mem::take(&mut self.focus_cursor) {
+                    response.request_focus();
+                }
+                if response.has_focus() {
+                    let filter = egui::EventFilter {horizontal_arrows: true, vertical_arrows: true, escape: true, tab: false};
+                    ui.memory_mut(|memory| memory.set_focus_lock_filter(id, filter));
+                    // two colors, so the focus is seen on every board style
+                    let painter = ui.painter();
+                    painter.rect_stroke(rect.shrink(2.), 2., egui::Stroke::new(4., egui::Color32::BLACK));
+                    painter.rect_stroke(rect.shrink(2.), 2., egui::Stroke::new(2., egui::Color32::WHITE));
+                    keys = ui.input(|input| {
+                        use egui::Key;
+                        [Key::ArrowLeft, Key::ArrowRight, Key::ArrowUp, Key::ArrowDown, Key::Enter, Key::Space, Key::Delete, Key::Backspace, Key::Escape]
+                            .into_iter()
+                            .filter(|&key| input.key_pressed(key))
+                            .collect()
+                    });
+                }
+            }
+        }
+        for key in keys {
+            self.on_key(key);
+        }
+    }
+
+    /// move typed like "22-18", "22x15x6" or "c3-d4" is played like clicks on its squares
+    fn play_typed_move(&mut self) {
+        let text = std::mem::take(&mut self.typed_move);
+        let mvs = match notation::parse_move(text.trim()) {
+            Ok(mvs) => mvs,
+            Err(e) => {
+                self.status_message = Some(format!("Bad move \"{}\": {}", text.trim(), e));
+                return;
+            },
+        };
+        for mv in mvs {
+            let ply = self.game.ply();
+            self.on_click(mv.from);
+            self.on_click(mv.to);
+            if self.game.ply() == ply {
+                self.status_message = Some(format!("Can't play {}", text.trim()));
+                return;
             }
+        }
+        self.status_message = None;
+    }
 
+    fn render_move_entry(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = ui.label("Move:");
+            let response = ui.add(egui::TextEdit::singleline(&mut self.typed_move).hint_text("22-18").desired_width(80.)).labelled_by(label.id);
+            if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                self.play_typed_move();
+                response.request_focus();
+            }
         });
     }
 
-    fn change_game_mode(&mut self, new_mode: GameMode) {
-        self.game_mode = new_mode;
-        self.restart();
+    fn piece_image(&self, cell: board::Cell) -> Option<egui::Image<'static>> {
+        self.theme.piece_uri(cell).map(egui::Image::from_uri)
     }
 
-    fn who_win(&self) -> String {
-        match self.bd.who_win() {
-            Some(board::Side::Black) => "Black".to_string(),
-            Some(board::Side::White) => "White".to_string(),
-            None => "No one".to_string(),
+    fn paint_piece(&self, ui: &egui::Ui, cell: board::Cell, rect: egui::Rect, opacity: f32) {
+        if let Some(image) = self.piece_image(cell) {
+            let tint = egui::Color32::from_white_alpha((opacity * 255.) as u8);
+            image.tint(tint).paint_at(ui, rect.shrink(rect.width() * 0.06));
         }
     }
-}
 
-impl eframe::App for App {
-    #[allow(unused_variables)]
-    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+    /// moves played since the last frame slide, jumps over the game like a restart don't
+    fn animate_new_moves(&mut self) {
+        let moves: Vec<board::Move> = self.game.history().iter().map(|ply| ply.mv).collect();
+        if moves == self.shown_moves {
+            return;
+        }
+        let shown = std::mem::replace(&mut self.shown_moves, moves.clone());
+        // a move and the answer to it at most
+        let recent = self.game.turns().iter().rev().filter(|turn| turn.first_ply < moves.len()).nth(1).map(|turn| turn.first_ply).unwrap_or(0);
+        if !moves.starts_with(&shown) || shown.len() < recent {
+            self.animation = None;
+            return;
+        }
+        let new_moves = &moves[shown.len()..];
+        match &mut self.animation {
+            Some(animation) if !animation.is_finished() => animation.extend(new_moves),
+            _ => {
+                let board = match shown.len() {
+                    0 => *self.game.start_board(),
+                    ply => self.game.history()[ply - 1].board,
+                };
+                self.animation = Some(board_view::Animation::new(board, new_moves.to_vec()));
+            },
+        }
+    }
 
+    /// moves made by dragging are already where they belong
+    fn skip_animation(&mut self) {
+        self.shown_moves = self.game.history().iter().map(|ply| ply.mv).collect();
+        self.animation = None;
     }
 
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui_extras::install_image_loaders(ctx);
+    fn square_at(geometry: &board_view::Geometry, pos: Option<egui::Pos2>) -> Option<board::Square> {
+        let (x, y) = geometry.xy_at(pos?)?;
+        board::Square::new(x, y).ok()
+    }
 
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-            egui::menu::bar(ui, |ui| {
-                ui.menu_button("Checkers", |ui| {
-                    ui.add(egui::Hyperlink::from_label_and_url(
-                            "Source code.",
-                            "https://github.com/e9000000000/checkers",
-                            ));
-                    if ui.button("Restart").clicked() {
-                        self.restart();
-                    }
-                    if ui.button("Quit").clicked() {
-                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                    }
-                });
-                ui.menu_button("Game mode", |ui| {
-                    if ui.radio(self.game_mode == GameMode::SelfPlay, "self play").clicked() {
-                        self.change_game_mode(GameMode::SelfPlay)
-                    }
-                    if ui.radio(self.game_mode == GameMode::Random, "random").clicked() {
-                        self.change_game_mode(GameMode::Random)
-                    }
-                    if ui.radio(self.game_mode == GameMode::MinMax5, "min max 5").clicked() {
-                        self.change_game_mode(GameMode::MinMax5)
-                    }
-                    if ui.radio(self.game_mode == GameMode::MinMax10, "min max 10").clicked() {
-                        self.change_game_mode(GameMode::MinMax10)
-                    }
-                    if ui.radio(self.game_mode == GameMode::MinMax15, "min max 15").clicked() {
-                        self.change_game_mode(GameMode::MinMax15)
-                    }
-                    if ui.radio(self.game_mode == GameMode::RandomVsRandom, "random vs random").clicked() {
-                        self.change_game_mode(GameMode::RandomVsRandom)
-                    }
-                });
-                ui.menu_button("Change side", |ui| {
-                    if ui.radio(self.player_side == board::Side::White, "white").clicked() {
-                        self.player_side = board::Side::White;
-                        self.restart();
-                    }
-                    if ui.radio(self.player_side == board::Side::Black, "black").clicked() {
-                        self.player_side = board::Side::Black;
-                        self.restart();
+    fn on_board_input(&mut self, geometry: &board_view::Geometry, response: &egui::Response) {
+        // the keyboard goes on from the last clicked square
+        if let Some(xy) = response.interact_pointer_pos().filter(|_| response.clicked()).and_then(|pos| geometry.xy_at(pos)) {
+            self.cursor = xy;
+        }
+        if let Some(editor) = &mut self.editor {
+            if let Some(square) = App::square_at(geometry, response.interact_pointer_pos()) {
+                if response.clicked() {
+                    editor.click(square);
+                } else if response.secondary_clicked() {
+                    editor.remove(square);
+                }
+            }
+            return;
+        }
+        if response.drag_started() {
+            let origin = response.ctx.input(|i| i.pointer.press_origin());
+            if let Some(square) = App::square_at(geometry, origin) {
+                if self.game.board().get_cell(square) != board::Cell::Empty {
+                    self.on_click(square);
+                    if self.selected_cell == Some(square) {
+                        self.dragged = Some(square);
                     }
-                });
-                ui.add_space(16.0);
-            });
-        });
+                }
+            }
+        }
+        if response.drag_released() {
+            if let Some(from) = self.dragged.take() {
+                match App::square_at(geometry, response.interact_pointer_pos()) {
+                    Some(to) if to != from => {
+                        self.on_click(to);
+                        self.skip_animation();
+                    },
+                    Some(_) => (),
+                    None => {
+                        self.selected_cell = None;
+                        self.highlight_available_checkers_to_move();
+                    },
+                }
+            }
+        } else if response.clicked() {
+            match App::square_at(geometry, response.interact_pointer_pos()) {
+                Some(square) => self.on_click(square),
+                None => {
+                    self.selected_cell = None;
+                    self.highlight_available_checkers_to_move();
+                },
+            }
+        }
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            self.render_board(ui);
-            self.show_game_ended_popup_if_game_ended();
-            if self.show_game_ended_popup {
-                egui::Window::new("End of the game").collapsible(false).anchor(egui::Align2::CENTER_CENTER, [0., 0.]).movable(true).show(ctx, |ui| {
-                    ui.with_layout(egui::Layout::top_down_justified(egui::Align::Center), |ui| {
-                        ui.label(format!("{} won", self.who_win()));
-                        if ui.button("restart").clicked() {
-                            self.restart();
-                        }
-                    });
-                });
+    fn render_board(&mut self, ui: &mut egui::Ui) {
+        let size = f32::min(ui.available_width(), ui.available_height());
+        let (rect, response) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::click_and_drag());
+        let geometry = board_view::Geometry {rect, flipped: self.player_side == board::Side::Black};
+        self.on_board_input(&geometry, &response);
+        self.animate_new_moves();
+        let frame = self.animation.as_ref().and_then(|animation| animation.frame());
+        if frame.is_some() {
+            ui.ctx().request_repaint();
+        }
+
+        let painter = ui.painter_at(rect);
+        let text_color = ui.visuals().text_color();
+        let font = egui::FontId::proportional(geometry.cell_size() * 0.3);
+        painter.text(geometry.cell_rect(0, 0).center(), egui::Align2::CENTER_CENTER, self.game.board().move_amount, font.clone(), text_color);
+        let coordinates = self.theme.coordinates;
+        for i in 1..9 {
+            let index = geometry.board_index(i).unwrap_or_default();
+            painter.text(geometry.cell_rect(i, 0).center(), egui::Align2::CENTER_CENTER, coordinates.column(index), font.clone(), text_color);
+            painter.text(geometry.cell_rect(0, i).center(), egui::Align2::CENTER_CENTER, coordinates.row(index), font.clone(), text_color);
+        }
+        let small_font = egui::FontId::proportional(geometry.cell_size() * 0.18);
+
+        let editing = self.editor.is_some();
+        let cell_at = |square| match (&self.editor, &frame) {
+            (Some(editor), _) => editor.builder.get_cell(square),
+            (None, Some(frame)) => frame.board.get_cell(square),
+            (None, None) => self.game.board().get_cell(square),
+        };
+        for y in 0..8 {
+            for x in 0..8 {
+                let square = board::Square::new(x, y).ok();
+                let color = match square {
+                    None => self.theme.board.light_square(),
+                    Some(square) if !editing && self.highlighted.contains(&square) => self.theme.palette.available(),
+                    Some(square) if !editing && self.moved_to == Some(square) => self.theme.palette.last_move(),
+                    Some(_) => self.theme.board.dark_square(),
+                };
+                let cell_rect = geometry.rect_at(x, y).shrink(1.);
+                painter.rect_filled(cell_rect, 2., color);
+
+                let square = match square {
+                    Some(square) => square,
+                    None => continue,
+                };
+                if let Some(label) = coordinates.square(square) {
+                    let corner = cell_rect.left_top() + egui::vec2(2., 1.);
+                    painter.text(corner, egui::Align2::LEFT_TOP, label, small_font.clone(), egui::Color32::GRAY);
+                }
+                let hidden = match &frame {
+                    Some(frame) => frame.slide.from == square || frame.fading.map(|(fading, _, _)| fading) == Some(square),
+                    None => self.dragged == Some(square),
+                };
+                if !hidden {
+                    self.paint_piece(ui, cell_at(square), cell_rect, 1.);
+                }
+                // gray stays readable on every board style in light and dark mode
+                if let Some(index) = self.moved_from.iter().position(|&r| r == square && !editing) {
+                    painter.text(cell_rect.center(), egui::Align2::CENTER_CENTER, index, font.clone(), egui::Color32::GRAY);
+                }
             }
-        });
+        }
+
+        let piece_rect = |center: egui::Pos2| egui::Rect::from_center_size(center, egui::vec2(geometry.cell_size(), geometry.cell_size()));
+        if let Some(frame) = &frame {
+            if let Some((square, cell, opacity)) = frame.fading {
+                self.paint_piece(ui, cell, geometry.square_rect(square).shrink(1.), opacity);
+            }
+            let from = geometry.square_rect(frame.slide.from).center();
+            let to = geometry.square_rect(frame.slide.to).center();
+            self.paint_piece(ui, frame.slide.cell, piece_rect(from.lerp(to, frame.slide.progress)), 1.);
+        } else if let (Some(square), Some(pos)) = (self.dragged, response.interact_pointer_pos()) {
+            self.paint_piece(ui, self.game.board().get_cell(square), piece_rect(pos), 1.);
+        }
+        if frame.is_none() && !editing {
+            self.paint_help(&painter, &geometry);
+        }
+        self.render_square_widgets(ui, &geometry);
+    }
+
+    /// threats and the hint over the pieces
+    fn paint_help(&self, painter: &egui::Painter, geometry: &board_view::Geometry) {
+        let width = geometry.cell_size() * 0.06;
+        if self.show_threats && self.help_allowed() && !self.is_reviewing() {
+            let side = self.human_side();
+            let board = self.game.board();
+            for (color, squares) in [
+                (self.theme.palette.threat(), board_view::capturable_pieces(board, !side)),
+                (self.theme.palette.en_prise(), board_view::capturable_pieces(board, side)),
+            ] {
+                for square in squares {
+                    let rect = geometry.square_rect(square);
+                    painter.circle_stroke(rect.center(), rect.width() * 0.45, egui::Stroke::new(width, color));
+                }
+            }
+        }
+        if let Some(hint) = &self.hint {
+            for mv in &hint.turn {
+                let from = geometry.square_rect(mv.from).center();
+                let to = geometry.square_rect(mv.to).center();
+                painter.arrow(from, to - from, egui::Stroke::new(width, self.theme.palette.hint()));
+            }
+        }
+    }
+
+    /// board shows an earlier position of the game
+    fn is_reviewing(&self) -> bool {
+        !self.game.is_at_end()
+    }
+
+    /// show position after `ply`, moves of its last turn are marked like fresh ones
+    fn go_to_ply(&mut self, ply: usize) {
+        if !self.game.go_to_ply(ply) {
+            return;
+        }
+        self.selected_cell = None;
+        self.move_error = None;
+        self.moved_from = vec![];
+        self.moved_to = None;
+        if let Some(turn) = self.game.turns().into_iter().find(|turn| turn.first_ply < ply && ply <= turn.first_ply + turn.mvs.len()) {
+            let mvs = &turn.mvs[..ply - turn.first_ply];
+            self.moved_from = mvs.iter().map(|mv| mv.from).collect();
+            self.moved_to = mvs.last().map(|mv| mv.to);
+        }
+        match self.is_reviewing() {
+            true => self.highlighted = vec![],
+            false => self.highlight_available_checkers_to_move(),
+        }
+    }
+
+    /// plies after every turn of the current line, the start is 0
+    fn turn_ends(&self) -> Vec<usize> {
+        let mut ends = vec![0];
+        ends.extend(self.game.turns().iter().map(|turn| turn.first_ply + turn.mvs.len()));
+        ends
+    }
+
+    fn go_to_previous_turn(&mut self) {
+        let ply = self.game.ply();
+        if let Some(&end) = self.turn_ends().iter().rev().find(|&&end| end < ply) {
+            self.go_to_ply(end);
+        }
+    }
+
+    fn go_to_next_turn(&mut self) {
+        let ply = self.game.ply();
+        if let Some(&end) = self.turn_ends().iter().find(|&&end| end > ply) {
+            self.go_to_ply(end);
+        }
+    }
+
+    /// side of the one who sits at the window, in self play it is the side to move
+    fn human_side(&self) -> board::Side {
+        match self.game_mode {
+            GameMode::SelfPlay | GameMode::Analysis => self.game.board().who_turn(),
+            _ => self.player_side,
+        }
+    }
+
+    /// a network game is played without help of the engine, the opponent can't see it
+    fn help_allowed(&self) -> bool {
+        self.game_mode != GameMode::Network
+    }
+
+    fn can_hint(&self) -> bool {
+        self.help_allowed()
+            && self.editor.is_none()
+            && self.thinker.is_none()
+            && !self.is_reviewing()
+            && self.game_mode != GameMode::RandomVsRandom
+            && self.game.result() == game::GameResult::Ongoing
+            && self.game.board().who_turn() == self.human_side()
+    }
+
+    fn ask_hint(&mut self) {
+        if !self.can_hint() {
+            return;
+        }
+        self.hint = Some(Hint {
+            moves: self.game.history().iter().map(|ply| ply.mv).collect(),
+            thinker: Some(ai::Thinker::start(ai::Player::MinMax(HINT_DEPTH), *self.game.board(), None)),
+            turn: vec![],
+        });
+    }
+
+    /// hint is dropped after any move or jump over the game
+    fn poll_hint(&mut self) {
+        let moves: Vec<board::Move> = match &self.hint {
+            Some(_) => self.game.history().iter().map(|ply| ply.mv).collect(),
+            None => return,
+        };
+        let hint = self.hint.as_mut().unwrap();
+        if hint.moves != moves || self.editor.is_some() {
+            self.hint = None;
+            return;
+        }
+        if let Some(turn) = hint.thinker.as_mut().and_then(|thinker| thinker.poll()) {
+            hint.thinker = None;
+            hint.turn = turn;
+        }
+    }
+
+    fn render_help(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.add_enabled(self.can_hint(), egui::Button::new("Hint")).clicked() {
+                self.ask_hint();
+            }
+            ui.add_enabled(self.help_allowed(), egui::Checkbox::new(&mut self.show_threats, "Threats"));
+        });
+        match &self.hint {
+            Some(hint) if hint.thinker.is_some() => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Looking for a hint…");
+                });
+            },
+            Some(hint) if !hint.turn.is_empty() => {
+                ui.label(format!("Hint: {}", notation::format_turn(self.game.board(), &hint.turn)));
+            },
+            _ => (),
+        }
+        if self.show_threats && self.help_allowed() {
+            ui.colored_label(self.theme.palette.threat(), "○ your pieces which can be taken");
+            ui.colored_label(self.theme.palette.en_prise(), "○ pieces you can take");
+        }
+        ui.separator();
+    }
+
+    fn render_history(&mut self, ui: &mut egui::Ui) {
+        let ply = self.game.ply();
+        let line = self.game.line();
+        let last = line.len();
+        ui.horizontal(|ui| {
+            if ui.add_enabled(ply > 0, egui::Button::new("<<")).on_hover_text("first").clicked() {
+                self.go_to_ply(0);
+            }
+            if ui.add_enabled(ply > 0, egui::Button::new("<")).on_hover_text("previous").clicked() {
+                self.go_to_previous_turn();
+            }
+            if ui.add_enabled(ply < last, egui::Button::new(">")).on_hover_text("next").clicked() {
+                self.go_to_next_turn();
+            }
+            if ui.add_enabled(ply < last, egui::Button::new(">>")).on_hover_text("last").clicked() {
+                self.go_to_ply(last);
+            }
+        });
+        if self.is_reviewing() {
+            ui.label("Reviewing, go to the last move to play");
+        }
+        ui.separator();
+
+        let mut clicked = None;
+        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+            egui::Grid::new("history_grid").num_columns(3).striped(true).show(ui, |ui| {
+                let mut number = 1;
+                for (i, turn) in self.game.turns().iter().enumerate() {
+                    match turn.side {
+                        board::Side::White => {
+                            ui.label(format!("{}.", number));
+                        },
+                        board::Side::Black if i == 0 => {
+                            ui.label(format!("{}.", number));
+                            ui.label("...");
+                        },
+                        board::Side::Black => (),
+                    }
+                    let end = turn.first_ply + turn.mvs.len();
+                    let current = turn.first_ply < ply && ply <= end;
+                    let mut label = ui.selectable_label(current, notation::format_turn(&turn.board, &turn.mvs));
+                    if let Some(spent) = turn_time(&line, turn) {
+                        label = label.on_hover_text(format!("played in {}", clock::format_time(spent)));
+                    }
+                    if label.clicked() {
+                        clicked = Some(end);
+                    }
+                    if turn.side == board::Side::Black {
+                        number += 1;
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+        if let Some(end) = clicked {
+            self.go_to_ply(end);
+        }
+    }
+
+    fn render_editor(&mut self, ui: &mut egui::Ui) {
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            None => return,
+        };
+        ui.heading("Set up position");
+        for (cell, name) in [
+            (board::Cell::White, "white man"),
+            (board::Cell::WhiteKing, "white king"),
+            (board::Cell::Black, "black man"),
+            (board::Cell::BlackKing, "black king"),
+            (board::Cell::Empty, "remove"),
+        ] {
+            ui.radio_value(&mut editor.tool, cell, name);
+        }
+        ui.label("Right click removes a piece");
+        ui.separator();
+
+        ui.label("To move");
+        let side = editor.builder.who_turn();
+        ui.horizontal(|ui| {
+            if ui.radio(side == board::Side::White, "white").clicked() {
+                editor.set_side(board::Side::White);
+            }
+            if ui.radio(side == board::Side::Black, "black").clicked() {
+                editor.set_side(board::Side::Black);
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Clear").clicked() {
+                editor.clear();
+            }
+            if ui.button("Start position").clicked() {
+                editor.start_position();
+            }
+        });
+        ui.separator();
+
+        ui.label("FEN");
+        ui.text_edit_singleline(&mut editor.fen);
+        ui.horizontal(|ui| {
+            if ui.button("Import").clicked() {
+                editor.import_fen();
+            }
+            if ui.button("Export").clicked() {
+                editor.export_fen();
+            }
+        });
+        if let Some(error) = &editor.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+        ui.separator();
+
+        let mut play = false;
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            play = ui.button("Play").clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+        if play {
+            self.play_setup();
+        } else if cancel {
+            self.editor = None;
+        }
+    }
+
+    /// start a game from the position of the editor if it is valid
+    fn play_setup(&mut self) {
+        let editor = match &mut self.editor {
+            Some(editor) => editor,
+            None => return,
+        };
+        match editor.board() {
+            Ok(board) => {
+                self.editor = None;
+                self.load_game(game::Game::from_board(board));
+            },
+            Err(e) => editor.error = Some(e),
+        }
+    }
+
+    /// network games have no clocks, the time of the other window can't be checked
+    fn new_clock(&self) -> Option<clock::Clock> {
+        match self.game_mode {
+            GameMode::Network | GameMode::Analysis => None,
+            _ => self.time_control.map(clock::Clock::new),
+        }
+    }
+
+    /// the clock of the side to move runs, the one who runs out of time loses
+    fn update_clock(&mut self, now: Instant) {
+        let clock = match &mut self.clock {
+            Some(clock) => clock,
+            None => return,
+        };
+        // nobody plays while a position is set up, the clock goes on after it
+        if self.game.result() != game::GameResult::Ongoing || self.editor.is_some() {
+            return clock.stop(now);
+        }
+        if let Some(side) = clock.flagged(now) {
+            clock.stop(now);
+            self.thinker = None;
+            self.game.set_result(game::GameResult::Win(!side));
+            self.status_message = Some(format!("{} lost on time", side));
+            return;
+        }
+        // the turn is made at the end of the game, even when an earlier position is shown
+        let to_move = match self.game.line().last() {
+            Some(ply) => ply.board.who_turn(),
+            None => self.game.start_board().who_turn(),
+        };
+        if clock.running_side() != Some(to_move) {
+            clock.press(to_move, now);
+        }
+    }
+
+    fn render_clock(&self, ui: &mut egui::Ui) {
+        let clock = match &self.clock {
+            Some(clock) => clock,
+            None => return,
+        };
+        let now = Instant::now();
+        // the same order as on the board
+        for side in [!self.player_side, self.player_side] {
+            let time = clock.remaining(side, now);
+            let mut text = egui::RichText::new(format!("{} {}", side, clock::format_time(time))).monospace().size(20.);
+            if clock.running_side() == Some(side) {
+                text = text.strong();
+            }
+            if time < Duration::from_secs(10) {
+                text = text.color(ui.visuals().error_fg_color);
+            }
+            ui.label(text);
+        }
+        ui.separator();
+    }
+
+    fn render_clock_menu(&mut self, ui: &mut egui::Ui) {
+        let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+        let seconds = Duration::from_secs;
+        let mut control = self.time_control;
+        if ui.radio(control.is_none(), "no clock").clicked() {
+            control = None;
+        }
+        for (name, default) in [
+            ("sudden death", clock::TimeControl::SuddenDeath {base: minutes(5)}),
+            ("Fischer increment", clock::TimeControl::Fischer {base: minutes(3), increment: seconds(2)}),
+            ("Bronstein delay", clock::TimeControl::Bronstein {base: minutes(3), delay: seconds(3)}),
+            ("moves per period", clock::TimeControl::MovesPerPeriod {moves: 20, period: minutes(5)}),
+        ] {
+            if ui.radio(control.map(|control| control.name()) == Some(default.name()), name).clicked() {
+                control = Some(default);
+            }
+        }
+
+        ui.separator();
+        let time_edit = |ui: &mut egui::Ui, label: &str, time: &mut Duration, unit: u64| {
+            ui.horizontal(|ui| {
+                let mut amount = time.as_secs() / unit;
+                ui.add(egui::DragValue::new(&mut amount).clamp_range(1..=600));
+                ui.label(label);
+                *time = Duration::from_secs(amount * unit);
+            });
+        };
+        match &mut control {
+            None => (),
+            Some(clock::TimeControl::SuddenDeath {base}) => time_edit(ui, "minutes", base, 60),
+            Some(clock::TimeControl::Fischer {base, increment}) => {
+                time_edit(ui, "minutes", base, 60);
+                time_edit(ui, "seconds added after a turn", increment, 1);
+            },
+            Some(clock::TimeControl::Bronstein {base, delay}) => {
+                time_edit(ui, "minutes", base, 60);
+                time_edit(ui, "seconds of delay", delay, 1);
+            },
+            Some(clock::TimeControl::MovesPerPeriod {moves, period}) => {
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(moves).clamp_range(1..=100));
+                    ui.label("turns");
+                });
+                time_edit(ui, "minutes for them", period, 60);
+            },
+        }
+        self.time_control = control;
+
+        ui.label("Computer thinks as long as its clock allows");
+        if ui.add_enabled(!self.is_guest(), egui::Button::new("Restart with this clock")).clicked() {
+            ui.close_menu();
+            self.restart();
+        }
+    }
+
+    /// continue `game` from its last position, network game is left for self play
+    fn load_game(&mut self, game: game::Game) {
+        if self.game_mode == GameMode::Network {
+            self.network = None;
+            self.game_mode = GameMode::SelfPlay;
+        }
+        self.thinker = None;
+        self.review = None;
+        self.game = game;
+        self.show_game_ended_popup = false;
+        self.selected_cell = None;
+        self.moved_from = vec![];
+        self.moved_to = None;
+        self.move_error = None;
+        self.draw_offered = false;
+        self.status_message = None;
+        self.go_to_ply(self.game.line().len());
+        self.clock = self.new_clock();
+        self.enemy_try_move();
+        self.highlight_available_checkers_to_move();
+    }
+
+    fn open_game(&mut self) {
+        let path = match rfd::FileDialog::new().add_filter("PDN", &["pdn"]).pick_file() {
+            Some(path) => path,
+            None => return,
+        };
+        let game = fs::read_to_string(&path).map_err(|e| e.to_string())
+            .and_then(|text| pdn::read(&text).map_err(|e| e.to_string()));
+        match game {
+            Ok(game) => self.load_game(game),
+            Err(e) => self.status_message = Some(format!("Can't open {}: {}", path.display(), e)),
+        }
+    }
+
+    /// event, date and players of a saved game, tags of an opened game are kept
+    fn tag_game(&mut self) {
+        let date = self.game.line().first().map_or(SystemTime::now(), |ply| ply.time);
+        let player = |side| match self.computer_plays() && (self.game_mode == GameMode::RandomVsRandom || side != self.player_side) {
+            true => "computer",
+            false => "player",
+        };
+        let tags = [
+            ("Event", "casual game".to_string()),
+            ("Date", pdn::format_date(date)),
+            ("White", player(board::Side::White).to_string()),
+            ("Black", player(board::Side::Black).to_string()),
+        ];
+        for (name, value) in tags {
+            if self.game.tag(name).is_none() {
+                self.game.set_tag(name, &value);
+            }
+        }
+    }
+
+    fn save_game(&mut self) {
+        let path = match rfd::FileDialog::new().add_filter("PDN", &["pdn"]).set_file_name("game.pdn").save_file() {
+            Some(path) => path,
+            None => return,
+        };
+        self.tag_game();
+        self.status_message = match fs::write(&path, pdn::write(&self.game)) {
+            Ok(()) => Some(format!("Saved to {}", path.display())),
+            Err(e) => Some(format!("Can't save {}: {}", path.display(), e)),
+        };
+    }
+
+    fn change_game_mode(&mut self, new_mode: GameMode) {
+        self.network = None;
+        self.status_message = None;
+        self.game_mode = new_mode;
+        // the game which was played is analyzed
+        if new_mode == GameMode::Analysis {
+            self.thinker = None;
+            self.clock = None;
+            self.draw_offered = false;
+            return self.highlight_available_checkers_to_move();
+        }
+        self.restart();
+    }
+
+    fn computer_plays(&self) -> bool {
+        !matches!(self.game_mode, GameMode::SelfPlay | GameMode::Network | GameMode::Analysis)
+    }
+
+    /// position the engine looks at, the shown one or the one in the editor
+    fn analyzed_board(&self) -> Option<board::Board> {
+        let board = match &self.editor {
+            Some(editor) => editor.board().ok()?,
+            None => *self.game.board(),
+        };
+        match board.is_ended() {
+            true => None,
+            false => Some(board),
+        }
+    }
+
+    /// search starts again whenever the position changes
+    fn update_analysis(&mut self) {
+        let board = match self.game_mode {
+            GameMode::Analysis => self.analyzed_board(),
+            _ => None,
+        };
+        let board = match board {
+            Some(board) => board,
+            None => {
+                self.analysis = None;
+                return;
+            },
+        };
+        let key = format!("{} {:?}", notation::format_fen(&board), board.jumping_piece());
+        match &mut self.analysis {
+            Some(analysis) if analysis.key == key => analysis.analyzer.poll(),
+            _ => self.analysis = Some(Analysis {key, board, analyzer: ai::Analyzer::start(board, ANALYSIS_LINES)}),
+        }
+    }
+
+    /// evaluation of the position, white's share grows from white's side of the board
+    fn render_eval_bar(&self, ui: &mut egui::Ui) {
+        let score = self.analysis.as_ref().and_then(|analysis| analysis.analyzer.analysis.as_ref()).and_then(|analysis| analysis.lines.first()).map(|line| line.score);
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), ui.available_height()), egui::Sense::hover());
+        let white_share = match score {
+            Some(100) => 1.,
+            Some(-100) => 0.,
+            Some(score) => (0.5 + score as f32 / 24.).clamp(0.05, 0.95),
+            None => 0.5,
+        };
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2., egui::Color32::BLACK);
+        let height = rect.height() * white_share;
+        let white_rect = match self.player_side {
+            board::Side::White => egui::Rect::from_min_max(egui::pos2(rect.min.x, rect.max.y - height), rect.max),
+            board::Side::Black => egui::Rect::from_min_max(rect.min, egui::pos2(rect.max.x, rect.min.y + height)),
+        };
+        painter.rect_filled(white_rect, 2., egui::Color32::WHITE);
+        if let Some(score) = score {
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, notation::format_score(score), egui::FontId::monospace(12.), egui::Color32::GRAY);
+        }
+    }
+
+    fn render_analysis(&self, ui: &mut egui::Ui) {
+        let analysis = match &self.analysis {
+            Some(analysis) => analysis,
+            None => {
+                if self.game_mode == GameMode::Analysis {
+                    ui.label("Nothing to analyze");
+                    ui.separator();
+                }
+                return;
+            },
+        };
+        match &analysis.analyzer.analysis {
+            Some(result) => {
+                ui.label(format!("Depth {}, {} nodes", result.depth, result.nodes));
+                for line in &result.lines {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.monospace(notation::format_score(line.score));
+                        ui.label(notation::format_line(&analysis.board, &line.mvs));
+                    });
+                }
+            },
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Analyzing…");
+                });
+            },
+        }
+        ui.separator();
+    }
+
+    fn start_review(&mut self) {
+        self.show_game_ended_popup = false;
+        self.review = Some(ai::Reviewer::start(self.game.clone(), review::DEFAULT_DEPTH));
+    }
+
+    /// bad turns of the reviewed game, a click shows the position before the turn
+    fn render_review(&mut self, ctx: &egui::Context) {
+        let reviewer = match &self.review {
+            Some(reviewer) => reviewer,
+            None => return,
+        };
+        let mut open = true;
+        let mut clicked = None;
+        egui::Window::new("Game review").open(&mut open).default_width(240.).show(ctx, |ui| {
+            let report = match &reviewer.report {
+                Some(report) => report,
+                None => {
+                    let (done, total) = reviewer.progress;
+                    ui.label(format!("Reviewing turn {} of {}", (done + 1).min(total), total));
+                    ui.add(egui::ProgressBar::new(done as f32 / total.max(1) as f32));
+                    return;
+                },
+            };
+            egui::Grid::new("review_summary").num_columns(5).striped(true).show(ui, |ui| {
+                for text in ["", "accuracy", "?!", "?", "??"] {
+                    ui.label(text);
+                }
+                ui.end_row();
+                for side in [board::Side::White, board::Side::Black] {
+                    ui.label(side.to_string());
+                    ui.label(report.accuracy(side).map(|accuracy| format!("{:.0}%", accuracy)).unwrap_or("-".to_string()));
+                    for judgement in [review::Judgement::Inaccuracy, review::Judgement::Mistake, review::Judgement::Blunder] {
+                        ui.label(report.count(side, judgement).to_string());
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            let bad: Vec<usize> = (0..report.turns.len()).filter(|&i| report.turns[i].judgement != review::Judgement::Good).collect();
+            if bad.is_empty() {
+                ui.label("No mistakes found");
+            }
+            egui::ScrollArea::vertical().max_height(240.).show(ui, |ui| {
+                for i in bad {
+                    let turn = &report.turns[i];
+                    let text = format!(
+                        "{} {}{} {}",
+                        review::turn_number(&report.turns, i),
+                        notation::format_turn(&turn.board, &turn.mvs),
+                        turn.judgement.mark(),
+                        turn.judgement.name(),
+                    );
+                    let current = self.game.ply() == turn.first_ply;
+                    if ui.selectable_label(current, text).clicked() {
+                        clicked = Some(turn.first_ply);
+                    }
+                    ui.label(format!(
+                        "better {} ({} instead of {})",
+                        notation::format_turn(&turn.board, &turn.best),
+                        notation::format_score(turn.best_score),
+                        notation::format_score(turn.score),
+                    ));
+                }
+            });
+        });
+        if let Some(ply) = clicked {
+            self.go_to_ply(ply);
+        }
+        if !open {
+            self.review = None;
+        }
+    }
+
+    fn render_view_menu(&mut self, ui: &mut egui::Ui) {
+        let theme = &mut self.theme;
+        ui.label("board");
+        for style in theme::BoardStyle::ALL {
+            ui.radio_value(&mut theme.board, style, style.name());
+        }
+        ui.separator();
+        ui.label("pieces");
+        for pieces in theme::PieceSet::ALL {
+            ui.radio_value(&mut theme.pieces, pieces, pieces.name());
+        }
+        if ui.button("Custom pieces folder").on_hover_text("images like white_king.svg or white_king.png replace the pieces").clicked() {
+            ui.close_menu();
+            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                theme.piece_dir = Some(theme::PieceDir::open(path));
+            }
+        }
+        if let Some(dir) = &theme.piece_dir {
+            ui.label(format!("{} of 4 images from {}", dir.found(), dir.path.display()));
+            if ui.button("Built in pieces").clicked() {
+                theme.piece_dir = None;
+            }
+        }
+        ui.separator();
+        ui.label("coordinates");
+        for coordinates in theme::Coordinates::ALL {
+            ui.radio_value(&mut theme.coordinates, coordinates, coordinates.name());
+        }
+        ui.separator();
+        let mut colorblind = theme.palette == theme::Palette::Colorblind;
+        if ui.checkbox(&mut colorblind, "colorblind colors").changed() {
+            theme.palette = if colorblind { theme::Palette::Colorblind } else { theme::Palette::Standard };
+        }
+        ui.checkbox(&mut theme.light, "light mode");
+    }
+
+    fn game_result(&self) -> String {
+        match self.game.board().state() {
+            board::State::Draw(reason) => format!("Draw: {}", reason),
+            _ => match self.game.result() {
+                game::GameResult::Win(board::Side::Black) => "Black won".to_string(),
+                game::GameResult::Win(board::Side::White) => "White won".to_string(),
+                game::GameResult::Draw => "Draw".to_string(),
+                game::GameResult::Ongoing => "No one won".to_string(),
+            },
+        }
+    }
+}
+
+impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let game_mode = match self.game_mode {
+            GameMode::Network => GameMode::SelfPlay,
+            game_mode => game_mode,
+        };
+        storage.set_string("game_mode", game_mode.name().to_string());
+        storage.set_string("player_side", self.player_side.to_string());
+        storage.set_string("network_address", self.network_address.clone());
+        storage.set_string("time_control", self.time_control.map(|control| control.to_string()).unwrap_or_default());
+        storage.set_string("show_threats", self.show_threats.to_string());
+        storage.set_string("board_style", self.theme.board.name().to_string());
+        storage.set_string("piece_set", self.theme.pieces.name().to_string());
+        storage.set_string("coordinates", self.theme.coordinates.name().to_string());
+        storage.set_string("palette", self.theme.palette.name().to_string());
+        storage.set_string("light_mode", self.theme.light.to_string());
+        let piece_dir = self.theme.piece_dir.as_ref().map(|dir| dir.path.display().to_string());
+        storage.set_string("piece_dir", piece_dir.unwrap_or_default());
+
+        // only unfinished local games are resumed
+        let resume = self.game_mode != GameMode::Network && self.game.result() == game::GameResult::Ongoing;
+        let moves = match resume {
+            true => self.game.line().iter().map(|ply| format!("{}-{}", ply.mv.from, ply.mv.to)).collect::<Vec<_>>().join(" "),
+            false => String::new(),
+        };
+        storage.set_string("game", moves);
+        storage.set_string("start", if resume { notation::format_fen(self.game.start_board()) } else { String::new() });
+        let clock = self.clock.as_ref().filter(|_| resume).map(|clock| clock.format(Instant::now()));
+        storage.set_string("clock", clock.unwrap_or_default());
+        // marks of a reviewed position don't belong to the last one
+        let marks = resume && !self.is_reviewing();
+        storage.set_string("moved_from", if marks { format_squares(&self.moved_from) } else { String::new() });
+        storage.set_string("moved_to", if marks { format_squares(&Vec::from_iter(self.moved_to)) } else { String::new() });
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if ctx.style().visuals.dark_mode == self.theme.light {
+            ctx.set_visuals(self.theme.visuals());
+        }
+        if self.network.is_some() {
+            self.poll_network();
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        if self.thinker.is_some() {
+            self.poll_thinker();
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+        self.update_clock(Instant::now());
+        self.poll_hint();
+        self.update_analysis();
+        if self.analysis.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+        if self.hint.as_ref().is_some_and(|hint| hint.thinker.is_some()) {
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+        if let Some(reviewer) = &mut self.review {
+            reviewer.poll();
+            if reviewer.report.is_none() {
+                ctx.request_repaint_after(Duration::from_millis(100));
+            }
+        }
+        if self.clock.as_ref().is_some_and(|clock| clock.running_side().is_some()) {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("Checkers", |ui| {
+                    ui.add(egui::Hyperlink::from_label_and_url(
+                            "Source code.",
+                            "https://github.com/e9000000000/checkers",
+                            ));
+                    if ui.add_enabled(!self.is_guest(), egui::Button::new("Restart")).clicked() {
+                        self.restart();
+                    }
+                    if ui.button("Save game").clicked() {
+                        ui.close_menu();
+                        self.save_game();
+                    }
+                    if ui.button("Open game").clicked() {
+                        ui.close_menu();
+                        self.open_game();
+                    }
+                    if ui.add_enabled(!self.game.line().is_empty(), egui::Button::new("Review game")).clicked() {
+                        ui.close_menu();
+                        self.start_review();
+                    }
+                    if ui.add_enabled(self.game_mode != GameMode::Network, egui::Button::new("Set up position")).clicked() {
+                        ui.close_menu();
+                        self.editor = Some(editor::Editor::new(self.game.board()));
+                    }
+                    if self.game_mode == GameMode::Network {
+                        if ui.add_enabled(self.can_play_network_game(), egui::Button::new("Offer a draw")).clicked() {
+                            self.send(network::Message::DrawOffer);
+                        }
+                        if ui.add_enabled(self.can_play_network_game(), egui::Button::new("Resign")).clicked() {
+                            self.game.set_result(game::GameResult::Win(!self.player_side));
+                            self.send(network::Message::Resign);
+                        }
+                    } else if ui.add_enabled(!self.is_reviewing(), egui::Button::new("Agree to a draw")).clicked() {
+                        self.thinker = None;
+                        self.game.agree_draw();
+                    }
+                    if ui.button("Quit").clicked() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+                ui.menu_button("Game mode", |ui| {
+                    if ui.radio(self.game_mode == GameMode::SelfPlay, "self play").clicked() {
+                        self.change_game_mode(GameMode::SelfPlay)
+                    }
+                    if ui.radio(self.game_mode == GameMode::Random, "random").clicked() {
+                        self.change_game_mode(GameMode::Random)
+                    }
+                    if ui.radio(self.game_mode == GameMode::MinMax5, "min max 5").clicked() {
+                        self.change_game_mode(GameMode::MinMax5)
+                    }
+                    if ui.radio(self.game_mode == GameMode::MinMax10, "min max 10").clicked() {
+                        self.change_game_mode(GameMode::MinMax10)
+                    }
+                    if ui.radio(self.game_mode == GameMode::MinMax15, "min max 15").clicked() {
+                        self.change_game_mode(GameMode::MinMax15)
+                    }
+                    if ui.radio(self.game_mode == GameMode::RandomVsRandom, "random vs random").clicked() {
+                        self.change_game_mode(GameMode::RandomVsRandom)
+                    }
+                    if ui.radio(self.game_mode == GameMode::Analysis, "analysis").clicked() {
+                        self.change_game_mode(GameMode::Analysis)
+                    }
+                    ui.separator();
+                    ui.label("over network");
+                    ui.text_edit_singleline(&mut self.network_address);
+                    if ui.button("Host game").clicked() {
+                        self.start_network(network::Role::Host);
+                    }
+                    if ui.button("Join game").clicked() {
+                        self.start_network(network::Role::Guest);
+                    }
+                });
+                ui.menu_button("Clock", |ui| self.render_clock_menu(ui));
+                ui.menu_button("View", |ui| self.render_view_menu(ui));
+                ui.add_enabled_ui(!self.is_guest(), |ui| ui.menu_button("Change side", |ui| {
+                    if ui.radio(self.player_side == board::Side::White, "white").clicked() {
+                        self.player_side = board::Side::White;
+                        self.restart();
+                    }
+                    if ui.radio(self.player_side == board::Side::Black, "black").clicked() {
+                        self.player_side = board::Side::Black;
+                        self.restart();
+                    }
+                }));
+                ui.add_space(16.0);
+            });
+        });
+
+        let mut status = vec![];
+        status.extend(self.network_status());
+        status.extend(self.status_message.clone());
+        status.extend(self.move_error.map(|e| format!("Move unavailable: {}", e)));
+        let thinking = self.thinking_status();
+        if !status.is_empty() || thinking.is_some() {
+            egui::TopBottomPanel::bottom("status_panel").show(ctx, |ui| {
+                if let Some(text) = thinking {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(text);
+                        if ui.add_enabled(self.cancel_ply().is_some(), egui::Button::new("Cancel")).clicked() {
+                            self.cancel_thinking();
+                        }
+                    });
+                }
+                for text in status {
+                    ui.label(text);
+                }
+            });
+        }
+
+        if self.draw_offered {
+            egui::Window::new("Draw offer").collapsible(false).anchor(egui::Align2::CENTER_CENTER, [0., 0.]).show(ctx, |ui| {
+                ui.label("Opponent offers a draw");
+                ui.horizontal(|ui| {
+                    if ui.button("accept").clicked() {
+                        self.draw_offered = false;
+                        self.game.agree_draw();
+                        self.send(network::Message::DrawAccept);
+                    }
+                    if ui.button("decline").clicked() {
+                        self.draw_offered = false;
+                        self.send(network::Message::DrawDecline);
+                    }
+                });
+            });
+        }
+
+        egui::SidePanel::right("history_panel").resizable(true).default_width(180.).show(ctx, |ui| {
+            self.render_clock(ui);
+            self.render_analysis(ui);
+            match self.editor.is_some() {
+                true => self.render_editor(ui),
+                false => {
+                    self.render_help(ui);
+                    self.render_move_entry(ui);
+                    self.render_history(ui);
+                },
+            }
+        });
+
+        if self.game_mode == GameMode::Analysis {
+            egui::SidePanel::left("eval_bar").resizable(false).exact_width(28.).show(ctx, |ui| {
+                self.render_eval_bar(ui);
+            });
+        }
+
+        self.render_review(ctx);
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            self.render_board(ui);
+            self.show_game_ended_popup_if_game_ended();
+            if self.show_game_ended_popup {
+                egui::Window::new("End of the game").collapsible(false).anchor(egui::Align2::CENTER_CENTER, [0., 0.]).movable(true).show(ctx, |ui| {
+                    ui.with_layout(egui::Layout::top_down_justified(egui::Align::Center), |ui| {
+                        ui.label(self.game_result());
+                        if !self.is_guest() && ui.button("restart").clicked() {
+                            self.restart();
+                        }
+                        if ui.button("review").clicked() {
+                            self.start_review();
+                        }
+                    });
+                });
+            }
+        });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    #[derive(Default)]
+    struct MemoryStorage(HashMap<String, String>);
+
+    impl eframe::Storage for MemoryStorage {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn sq(number: usize) -> board::Square {
+        board::Square::from_number(number).unwrap()
+    }
+
+    fn wait_for_ai(app: &mut App) {
+        let start = Instant::now();
+        while app.thinker.is_some() {
+            assert!(start.elapsed() < Duration::from_secs(30), "computer thinks too long");
+            app.poll_thinker();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn resume_game_and_settings() {
+        let mut app = App::new();
+        app.change_game_mode(GameMode::MinMax5);
+        app.on_click(sq(22));
+        app.on_click(sq(18));
+        wait_for_ai(&mut app);
+        assert_eq!(app.game.ply(), 2);
+
+        let mut storage = MemoryStorage::default();
+        eframe::App::save(&mut app, &mut storage);
+        let resumed = App::resumed(Some(&storage));
+        assert!(resumed.game_mode == GameMode::MinMax5);
+        assert_eq!(resumed.player_side, board::Side::White);
+        assert_eq!(resumed.game.history().iter().map(|ply| ply.mv).collect::<Vec<_>>(), app.game.history().iter().map(|ply| ply.mv).collect::<Vec<_>>());
+        assert_eq!(resumed.moved_from, app.moved_from);
+        assert_eq!(resumed.moved_to, app.moved_to);
+    }
+
+    #[test]
+    fn theme_is_saved() {
+        let mut app = App::new();
+        app.theme = theme::Theme {
+            board: theme::BoardStyle::Wood,
+            pieces: theme::PieceSet::Flat,
+            piece_dir: Some(theme::PieceDir::open("pieces".into())),
+            coordinates: theme::Coordinates::Algebraic,
+            palette: theme::Palette::Colorblind,
+            light: true,
+        };
+        let mut storage = MemoryStorage::default();
+        eframe::App::save(&mut app, &mut storage);
+        assert_eq!(App::resumed(Some(&storage)).theme, app.theme);
+
+        eframe::Storage::set_string(&mut storage, "board_style", "marble".to_string());
+        assert_eq!(App::resumed(Some(&storage)).theme.board, theme::BoardStyle::Classic);
+    }
+
+    #[test]
+    fn resume_game_from_setup() {
+        let mut app = App::new();
+        app.editor = Some(editor::Editor::new(app.game.board()));
+        let editor = app.editor.as_mut().unwrap();
+        editor.fen = "W:W22,21:B1,2".to_string();
+        editor.import_fen();
+        app.play_setup();
+        app.on_click(sq(22));
+        app.on_click(sq(17));
+
+        let mut storage = MemoryStorage::default();
+        eframe::App::save(&mut app, &mut storage);
+        let resumed = App::resumed(Some(&storage));
+        assert_eq!(notation::format_fen(resumed.game.start_board()), "W:W21,22:B1,2");
+        assert_eq!(notation::format_fen(resumed.game.board()), notation::format_fen(app.game.board()));
+        assert_eq!(resumed.game.ply(), 1);
+    }
+
+    #[test]
+    fn resume_opened_game_with_fen() {
+        let mut app = App::new();
+        app.load_game(pdn::read("[FEN \"B:W21,22,30:B5,10\"]\n1... 10-14 *").unwrap());
+        assert_eq!(app.game.ply(), 1);
+
+        let mut storage = MemoryStorage::default();
+        eframe::App::save(&mut app, &mut storage);
+        let resumed = App::resumed(Some(&storage));
+        assert_eq!(notation::format_fen(resumed.game.start_board()), "B:W21,22,30:B5,10");
+        assert_eq!(resumed.game.line().len(), 1);
+        assert_eq!(resumed.moved_to, Some(sq(14)));
+    }
+
+    #[test]
+    fn resume_timed_game() {
+        let mut app = App::new();
+        app.game_mode = GameMode::SelfPlay;
+        app.time_control = Some(clock::TimeControl::Fischer {base: Duration::from_secs(10), increment: Duration::from_secs(1)});
+        app.restart();
+        let start = Instant::now();
+        app.update_clock(start);
+        app.on_click(sq(22));
+        app.on_click(sq(18));
+        app.update_clock(start + Duration::from_secs(4));
+
+        let mut storage = MemoryStorage::default();
+        eframe::App::save(&mut app, &mut storage);
+        let resumed = App::resumed(Some(&storage));
+        let clock = resumed.clock.as_ref().unwrap();
+        assert_eq!(clock.running_side(), None);
+        assert_eq!(clock.remaining(board::Side::White, Instant::now()), Duration::from_secs(7));
+
+        // a game from the older settings has no saved clock
+        storage.0.insert("clock".to_string(), String::new());
+        assert!(App::resumed(Some(&storage)).clock.is_none());
+    }
+
+    #[test]
+    fn tags_of_saved_game() {
+        let mut app = App::new();
+        app.change_game_mode(GameMode::MinMax5);
+        app.tag_game();
+        assert_eq!(app.game.tag("Event"), Some("casual game"));
+        assert_eq!(app.game.tag("Date"), Some(pdn::format_date(SystemTime::now()).as_str()));
+        assert_eq!(app.game.tag("White"), Some("player"));
+        assert_eq!(app.game.tag("Black"), Some("computer"));
+        assert!(pdn::write(&app.game).starts_with("[Event \"casual game\"]\n[Date "));
+
+        app.load_game(pdn::read("[Event \"club\"]\n[White \"Ann\"]\n1. 22-18 *").unwrap());
+        app.tag_game();
+        assert_eq!(app.game.tag("Event"), Some("club"));
+        assert_eq!(app.game.tag("White"), Some("Ann"));
+        assert_eq!(app.game.tag("Black"), Some("computer"));
+    }
+
+    #[test]
+    fn time_of_turns() {
+        let game = pdn::read("1. 22-18 11-15 2. 18x11 *").unwrap();
+        let line = game.line();
+        let turns = game.turns();
+        assert_eq!(turn_time(&line, &turns[0]), None);
+        assert!(turn_time(&line, &turns[2]).is_some());
+    }
+
+    #[test]
+    fn broken_storage_starts_new_game() {
+        let mut storage = MemoryStorage::default();
+        eframe::Storage::set_string(&mut storage, "game_mode", "unknown".to_string());
+        eframe::Storage::set_string(&mut storage, "game", "22-18 22-18".to_string());
+        let app = App::resumed(Some(&storage));
+        assert!(app.game_mode == GameMode::SelfPlay);
+        assert_eq!(app.game.ply(), 0);
+    }
+
+    #[test]
+    fn load_game_continues_it() {
+        let game = pdn::read("1. 22-18 11-15 2. 18x11 *").unwrap();
+        let mut app = App::new();
+        app.game_mode = GameMode::MinMax5;
+        app.load_game(game);
+        wait_for_ai(&mut app);
+        // the min max player answers with black
+        assert_eq!(app.game.ply(), 4);
+        assert_eq!(app.game.board().who_turn(), board::Side::White);
+        assert!(!app.highlighted.is_empty());
+
+        let mut app = App::new();
+        app.game_mode = GameMode::Network;
+        app.load_game(pdn::read("1. 22-18 *").unwrap());
+        assert!(app.game_mode == GameMode::SelfPlay);
+        assert_eq!(app.moved_from, vec![sq(22)]);
+        assert_eq!(app.moved_to, Some(sq(18)));
+    }
+
+    #[test]
+    fn review_history() {
+        let mut app = App::new();
+        app.load_game(pdn::read("1. 22-18 11-15 2. 18x11 8x15 *").unwrap());
+        assert_eq!(app.turn_ends(), vec![0, 1, 2, 3, 4]);
+
+        app.go_to_previous_turn();
+        assert!(app.is_reviewing());
+        assert_eq!(app.game.ply(), 3);
+        assert_eq!(app.moved_from, vec![sq(18)]);
+        assert!(app.highlighted.is_empty());
+        // the board is read only
+        app.on_click(sq(12));
+        assert!(app.selected_cell.is_none());
+
+        app.go_to_ply(0);
+        assert_eq!(app.moved_to, None);
+        app.go_to_previous_turn();
+        assert_eq!(app.game.ply(), 0);
+        app.go_to_next_turn();
+        assert_eq!(app.game.ply(), 1);
+
+        app.go_to_ply(4);
+        assert!(!app.is_reviewing());
+        assert!(!app.highlighted.is_empty());
+        assert_eq!(app.game.line().len(), 4);
+    }
+
+    #[test]
+    fn think_in_background() {
+        let mut app = App::new();
+        app.change_game_mode(GameMode::MinMax15);
+        app.on_click(sq(22));
+        app.on_click(sq(18));
+        assert!(app.thinker.is_some());
+        assert!(app.highlighted.is_empty());
+        assert!(app.thinking_status().is_some());
+
+        // earlier positions can be looked at, the board doesn't take moves
+        app.go_to_ply(0);
+        assert!(app.is_reviewing());
+        app.go_to_ply(1);
+        app.on_click(sq(11));
+        assert!(app.selected_cell.is_none());
+
+        assert_eq!(app.cancel_ply(), Some(0));
+        app.cancel_thinking();
+        assert!(app.thinker.is_none());
+        assert_eq!(app.game.ply(), 0);
+        assert!(!app.is_reviewing());
+        assert!(!app.highlighted.is_empty());
+
+        app.change_game_mode(GameMode::MinMax5);
+        app.on_click(sq(21));
+        app.on_click(sq(17));
+        app.go_to_ply(0);
+        wait_for_ai(&mut app);
+        // the answer is played at the end, the reviewed position stays
+        assert_eq!(app.game.ply(), 0);
+        assert_eq!(app.game.line().len(), 2);
+        assert_eq!(app.game.line()[0].mv, board::Move::new(sq(21), sq(17)));
+
+        // the computer moves first, its thinking can't be taken back
+        let mut app = App::new();
+        app.player_side = board::Side::Black;
+        app.change_game_mode(GameMode::MinMax15);
+        assert!(app.thinker.is_some());
+        assert_eq!(app.cancel_ply(), None);
+        app.cancel_thinking();
+        assert!(app.thinker.is_some());
+    }
+
+    #[test]
+    fn animate_only_recent_moves() {
+        let mut app = App::new();
+        app.on_click(sq(22));
+        app.on_click(sq(18));
+        app.animate_new_moves();
+        assert!(app.animation.as_ref().is_some_and(|animation| !animation.is_finished()));
+
+        // moves made by dragging are not animated again
+        app.on_click(sq(11));
+        app.on_click(sq(15));
+        app.skip_animation();
+        app.animate_new_moves();
+        assert!(app.animation.is_none());
+
+        app.load_game(pdn::read("1. 22-18 11-15 2. 18x11 8x15 3. 21-17 *").unwrap());
+        app.animate_new_moves();
+        assert!(app.animation.is_none());
+        app.go_to_ply(4);
+        app.animate_new_moves();
+        app.go_to_next_turn();
+        app.animate_new_moves();
+        assert!(app.animation.is_some());
+    }
+
+    #[test]
+    fn play_from_setup() {
+        let mut app = App::new();
+        app.editor = Some(editor::Editor::new(app.game.board()));
+        app.editor.as_mut().unwrap().clear();
+        app.play_setup();
+        assert!(app.editor.as_ref().is_some_and(|editor| editor.error.is_some()));
+
+        let editor = app.editor.as_mut().unwrap();
+        editor.fen = "B:W22:B18,10".to_string();
+        editor.import_fen();
+        app.play_setup();
+        assert!(app.editor.is_none());
+        assert_eq!(app.game.board().who_turn(), board::Side::Black);
+        assert_eq!(notation::format_fen(app.game.start_board()), "B:W22:B10,18");
+        assert_eq!(app.highlighted, vec![sq(18)]);
+    }
+
+    #[test]
+    fn loss_on_time() {
+        let mut app = App::new();
+        app.time_control = Some(clock::TimeControl::Fischer {base: Duration::from_secs(10), increment: Duration::from_secs(1)});
+        app.restart();
+        let start = Instant::now();
+        app.update_clock(start);
+        assert_eq!(app.clock.as_ref().and_then(|clock| clock.running_side()), Some(board::Side::White));
+
+        app.on_click(sq(22));
+        app.on_click(sq(18));
+        app.update_clock(start + Duration::from_secs(4));
+        let clock = app.clock.as_ref().unwrap();
+        assert_eq!(clock.running_side(), Some(board::Side::Black));
+        assert_eq!(clock.remaining(board::Side::White, start + Duration::from_secs(4)), Duration::from_secs(7));
+
+        app.update_clock(start + Duration::from_secs(15));
+        assert_eq!(app.game.result(), game::GameResult::Win(board::Side::White));
+        assert_eq!(app.status_message.as_deref(), Some("black lost on time"));
+    }
+
+    #[test]
+    fn clock_stops_in_editor() {
+        let mut app = App::new();
+        app.time_control = Some(clock::TimeControl::SuddenDeath {base: Duration::from_secs(10)});
+        app.restart();
+        let start = Instant::now();
+        app.update_clock(start);
+        app.editor = Some(editor::Editor::new(app.game.board()));
+        app.update_clock(start + Duration::from_secs(2));
+        app.update_clock(start + Duration::from_secs(30));
+        let clock = app.clock.as_ref().unwrap();
+        assert_eq!(clock.running_side(), None);
+        assert_eq!(clock.remaining(board::Side::White, start + Duration::from_secs(30)), Duration::from_secs(8));
+        assert_eq!(app.game.result(), game::GameResult::Ongoing);
+
+        app.editor = None;
+        app.update_clock(start + Duration::from_secs(31));
+        assert_eq!(app.clock.as_ref().and_then(|clock| clock.running_side()), Some(board::Side::White));
+    }
+
+    #[test]
+    fn hint_for_the_player() {
+        let mut app = App::new();
+        app.change_game_mode(GameMode::MinMax5);
+        app.ask_hint();
+        let start = Instant::now();
+        while app.hint.as_ref().is_some_and(|hint| hint.thinker.is_some()) {
+            assert!(start.elapsed() < Duration::from_secs(30), "hint takes too long");
+            app.poll_hint();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let turn = app.hint.as_ref().unwrap().turn.clone();
+        assert_eq!(turn.len(), 1);
+        assert!(app.game.board().clone().all_available_moves().contains(&turn[0]));
+
+        app.on_click(turn[0].from);
+        app.on_click(turn[0].to);
+        app.poll_hint();
+        assert!(app.hint.is_none());
+        // it is the computer's turn
+        assert!(!app.can_hint());
+
+        let mut app = App::new();
+        app.game_mode = GameMode::Network;
+        app.player_side = board::Side::White;
+        assert!(!app.can_hint());
+        app.ask_hint();
+        assert!(app.hint.is_none());
+    }
+
+    #[test]
+    fn analysis_follows_shown_position() {
+        let mut app = App::new();
+        app.on_click(sq(22));
+        app.on_click(sq(18));
+        app.change_game_mode(GameMode::Analysis);
+        assert_eq!(app.game.ply(), 1);
+        app.update_analysis();
+        let key = app.analysis.as_ref().unwrap().key.clone();
+
+        app.go_to_ply(0);
+        app.update_analysis();
+        assert_ne!(app.analysis.as_ref().unwrap().key, key);
+        assert_eq!(notation::format_fen(&app.analysis.as_ref().unwrap().board), notation::format_fen(&board::Board::new()));
+
+        // invalid setup in the editor has nothing to analyze
+        app.editor = Some(editor::Editor::new(app.game.board()));
+        app.editor.as_mut().unwrap().clear();
+        app.update_analysis();
+        assert!(app.analysis.is_none());
+
+        app.editor = None;
+        app.change_game_mode(GameMode::SelfPlay);
+        app.update_analysis();
+        assert!(app.analysis.is_none());
+        assert_eq!(notation::format_score(-3), "-3");
+        assert_eq!(notation::format_score(100), "white wins");
+    }
+
+    #[test]
+    fn review_finished_game() {
+        let mut app = App::new();
+        app.load_game(pdn::read("1. 22-18 11-15 2. 18x11 8x15 1-0").unwrap());
+        app.show_game_ended_popup_if_game_ended();
+        assert!(app.show_game_ended_popup);
+
+        app.start_review();
+        app.show_game_ended_popup_if_game_ended();
+        assert!(!app.show_game_ended_popup);
+        let start = Instant::now();
+        let reviewer = app.review.as_mut().unwrap();
+        while reviewer.report.is_none() {
+            assert!(start.elapsed() < Duration::from_secs(30), "review takes too long");
+            reviewer.poll();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(reviewer.report.as_ref().unwrap().turns.len(), 4);
+        assert_eq!(reviewer.progress, (4, 4));
+
+        app.restart();
+        assert!(app.review.is_none());
+    }
+
+    #[test]
+    fn keyboard_play() {
+        let mut app = App::new();
+        // from 22 to 18 and back to 22 by the cursor
+        app.on_key(egui::Key::Enter);
+        assert_eq!(app.selected_cell, Some(sq(22)));
+        assert!(app.describe_square(2, 5).contains("selected"));
+        app.on_key(egui::Key::ArrowUp);
+        app.on_key(egui::Key::ArrowRight);
+        assert!(app.focus_cursor);
+        assert_eq!(app.describe_square(app.cursor.0, app.cursor.1), "18 d4, empty, can move here");
+        app.on_key(egui::Key::Space);
+        assert_eq!(app.game.board().get_cell(sq(18)), board::Cell::White);
+        assert_eq!(app.describe_square(0, 0), "a8, light square");
+
+        app.typed_move = "11-15".to_string();
+        app.play_typed_move();
+        assert_eq!(app.game.ply(), 2);
+        assert!(app.typed_move.is_empty());
+        app.typed_move = "18-14".to_string();
+        app.play_typed_move();
+        assert_eq!(app.game.ply(), 2);
+        assert_eq!(app.status_message.as_deref(), Some("Can't play 18-14"));
+        app.typed_move = "18x11".to_string();
+        app.play_typed_move();
+        assert_eq!(app.game.ply(), 3);
+        assert_eq!(app.status_message, None);
+        assert!(app.describe_square(sq(11).x(), sq(11).y()).starts_with("11 f6, white man"));
     }
 }
@@ -0,0 +1,409 @@
+//! engine protocol for external programs, modelled on the Hub protocol of Scan-like draughts engines
+//!
+//! every message is one line: a command followed by `name=value` arguments,
+//! values with spaces are quoted, like `pos pos=Wbbbbbbbbbbbbeeeeeeeewwwwwwwwwwww moves="22-18 11-15"`
+//!
+//! a position is the side to move (`W` or `B`) and 32 cells in order of square numbers,
+//! `w`/`b` is a man, `W`/`B` is a king and `e` is an empty square
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, BoardBuilder, Cell, Side, Square};
+use crate::notation;
+use crate::player_minmax;
+
+const DEFAULT_DEPTH: usize = 10;
+/// search never goes deeper, it is the limit for `level infinite` and `level move-time`
+const MAX_DEPTH: usize = 64;
+
+type Output = Arc<Mutex<dyn Write + Send>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HubError {
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    BadValue(String),
+    BadPosition(String),
+    BadMove(String),
+}
+
+impl fmt::Display for HubError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HubError::UnknownCommand(s) => write!(f, "unknown command \"{}\"", s),
+            HubError::MissingArgument(s) => write!(f, "missing argument \"{}\"", s),
+            HubError::BadValue(s) => write!(f, "bad value \"{}\"", s),
+            HubError::BadPosition(s) => write!(f, "bad position: {}", s),
+            HubError::BadMove(s) => write!(f, "bad move: {}", s),
+        }
+    }
+}
+
+impl Error for HubError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Depth(usize),
+    MoveTime(Duration),
+    Infinite,
+}
+
+/// split a line into the command and its `name=value` arguments, a name without value has empty value
+fn parse_line(line: &str) -> Option<(String, Vec<(String, String)>)> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_quotes = false;
+    for c in line.trim().chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            },
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    let mut words = words.into_iter();
+    let command = words.next()?;
+    let args = words.map(|word| match word.split_once('=') {
+        Some((name, value)) => (name.to_string(), value.to_string()),
+        None => (word, String::new()),
+    }).collect();
+    Some((command, args))
+}
+
+fn arg<'a>(args: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    args.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+}
+
+pub fn parse_position(s: &str) -> Result<Board, HubError> {
+    let mut chars = s.chars();
+    let side = match chars.next() {
+        Some('W') => Side::White,
+        Some('B') => Side::Black,
+        _ => return Err(HubError::BadPosition("side to move must be W or B".to_string())),
+    };
+    let cells: Vec<char> = chars.collect();
+    if cells.len() != 32 {
+        return Err(HubError::BadPosition(format!("needs 32 squares, got {}", cells.len())));
+    }
+
+    let mut builder = BoardBuilder::new().side(side);
+    for (i, &c) in cells.iter().enumerate() {
+        let cell = match c {
+            'w' => Cell::White,
+            'b' => Cell::Black,
+            'W' => Cell::WhiteKing,
+            'B' => Cell::BlackKing,
+            'e' => Cell::Empty,
+            _ => return Err(HubError::BadPosition(format!("unknown piece '{}'", c))),
+        };
+        if cell != Cell::Empty {
+            let square = Square::from_number(i + 1).map_err(|e| HubError::BadPosition(e.to_string()))?;
+            builder = builder.piece(square, cell);
+        }
+    }
+    builder.build().map_err(|e| HubError::BadPosition(e.to_string()))
+}
+
+/// play turns like "22-18" or "22x15x8" one after another
+fn apply_moves(board: &mut Board, moves: &str) -> Result<(), HubError> {
+    for turn in moves.split_whitespace() {
+        let side = board.who_turn();
+        let mvs = notation::parse_move(turn).map_err(|e| HubError::BadMove(format!("{} ({})", turn, e)))?;
+        for mv in mvs {
+            board.do_move(mv).map_err(|e| HubError::BadMove(format!("{} ({})", turn, e)))?;
+        }
+        if board.who_turn() == side && !board.is_ended() {
+            return Err(HubError::BadMove(format!("{} (turn is not finished)", turn)));
+        }
+    }
+    Ok(())
+}
+
+fn send(output: &Output, line: &str) {
+    let mut output = output.lock().unwrap();
+    let _ = writeln!(output, "{}", line);
+    let _ = output.flush();
+}
+
+/// search in a background thread, prints `info` after every depth and `done` at the end
+fn think(board: Board, level: Level, stop: Arc<AtomicBool>, output: Output) {
+    let start = Instant::now();
+    let (max_depth, deadline) = match level {
+        Level::Depth(depth) => (depth, None),
+        // a time too long for the clock has no deadline
+        Level::MoveTime(time) => (MAX_DEPTH, start.checked_add(time)),
+        Level::Infinite => (MAX_DEPTH, None),
+    };
+    let sign = match board.who_turn() {
+        Side::White => 1,
+        Side::Black => -1,
+    };
+
+    let result = player_minmax::search(&board, max_depth, deadline, &stop, |info| {
+        let pv = match info.mv {
+            Some(mv) => notation::format_turn(&board, &player_minmax::complete_turn(&board, mv, (info.depth - 1).min(DEFAULT_DEPTH))),
+            None => String::new(),
+        };
+        send(&output, &format!(
+            "info depth={} score={} nodes={} time={:.3} pv=\"{}\"",
+            info.depth, sign * info.score as i32, info.nodes, start.elapsed().as_secs_f64(), pv,
+        ));
+    });
+
+    // `go analyze` waits for `stop` even when the search is over
+    if level == Level::Infinite {
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    let first = match result.and_then(|info| info.mv) {
+        Some(mv) => Some((mv, result.map(|info| info.depth).unwrap_or(1))),
+        None => board.clone().all_available_moves().first().map(|&mv| (mv, 1)),
+    };
+    match first {
+        Some((mv, depth)) => {
+            let turn = notation::format_turn(&board, &player_minmax::complete_turn(&board, mv, (depth - 1).min(DEFAULT_DEPTH)));
+            send(&output, &format!("done move={}", turn));
+        },
+        None => send(&output, "done"),
+    }
+}
+
+struct Engine {
+    board: Board,
+    level: Level,
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<()>>,
+    output: Output,
+}
+
+impl Engine {
+    fn new(output: Output) -> Self {
+        Engine {
+            board: Board::new(),
+            level: Level::Depth(DEFAULT_DEPTH),
+            stop: Arc::new(AtomicBool::new(false)),
+            search: None,
+            output,
+        }
+    }
+
+    fn stop_search(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(search) = self.search.take() {
+            let _ = search.join();
+        }
+    }
+
+    fn level(&mut self, args: &[(String, String)]) -> Result<(), HubError> {
+        if arg(args, "infinite").is_some() {
+            self.level = Level::Infinite;
+        } else if let Some(depth) = arg(args, "depth") {
+            let depth = depth.parse().map_err(|_| HubError::BadValue(depth.to_string()))?;
+            self.level = Level::Depth(depth);
+        } else if let Some(time) = arg(args, "move-time") {
+            let bad = || HubError::BadValue(time.to_string());
+            let seconds: f64 = time.parse().map_err(|_| bad())?;
+            self.level = Level::MoveTime(Duration::try_from_secs_f64(seconds.max(0.0)).map_err(|_| bad())?);
+        }
+        Ok(())
+    }
+
+    fn go(&mut self, args: &[(String, String)]) -> Result<(), HubError> {
+        self.stop_search();
+        let level = match arg(args, "analyze") {
+            Some(_) => Level::Infinite,
+            None => self.level,
+        };
+        self.stop = Arc::new(AtomicBool::new(false));
+        let board = self.board;
+        let stop = self.stop.clone();
+        let output = self.output.clone();
+        self.search = Some(thread::spawn(move || think(board, level, stop, output)));
+        Ok(())
+    }
+
+    /// handle one line, return false when the engine must quit
+    fn handle_line(&mut self, line: &str) -> Result<bool, HubError> {
+        let (command, args) = match parse_line(line) {
+            Some(parsed) => parsed,
+            None => return Ok(true),
+        };
+        match command.as_str() {
+            "hub" => {
+                send(&self.output, &format!("id name=checkers version={}", env!("CARGO_PKG_VERSION")));
+                send(&self.output, &format!("param name=depth value={} type=int min=1 max={}", DEFAULT_DEPTH, MAX_DEPTH));
+                send(&self.output, "wait");
+            },
+            "init" => send(&self.output, "ready"),
+            "ping" => send(&self.output, "pong"),
+            "new-game" => {
+                self.stop_search();
+                self.board = Board::new();
+            },
+            "pos" => {
+                self.stop_search();
+                let mut board = match arg(&args, "pos") {
+                    Some(pos) => parse_position(pos)?,
+                    None => Board::new(),
+                };
+                if let Some(moves) = arg(&args, "moves") {
+                    apply_moves(&mut board, moves)?;
+                }
+                self.board = board;
+            },
+            "level" => self.level(&args)?,
+            "set-param" => {
+                if arg(&args, "name") == Some("depth") {
+                    let value = arg(&args, "value").ok_or(HubError::MissingArgument("value"))?;
+                    let depth = value.parse().map_err(|_| HubError::BadValue(value.to_string()))?;
+                    self.level = Level::Depth(depth);
+                }
+            },
+            "go" => self.go(&args)?,
+            "stop" => self.stop_search(),
+            "ponder-hit" => (),
+            "quit" => {
+                self.stop_search();
+                return Ok(false);
+            },
+            _ => return Err(HubError::UnknownCommand(command)),
+        }
+        Ok(true)
+    }
+}
+
+/// talk the protocol over stdin and stdout until `quit` or end of input
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let output: Output = Arc::new(Mutex::new(io::stdout()));
+    let mut engine = Engine::new(output.clone());
+    for line in io::stdin().lock().lines() {
+        match engine.handle_line(&line?) {
+            Ok(true) => (),
+            Ok(false) => return Ok(()),
+            Err(e) => send(&output, &format!("error message=\"{}\"", e)),
+        }
+    }
+    // let the last search print its move before leaving
+    if let Some(search) = engine.search.take() {
+        let _ = search.join();
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// position in the form `parse_position` reads, the engine never sends one
+    fn format_position(board: &Board) -> String {
+        let mut result = match board.who_turn() {
+            Side::White => "W".to_string(),
+            Side::Black => "B".to_string(),
+        };
+        for number in 1..=32 {
+            let square = Square::from_number(number).unwrap();
+            result.push(match board.get_cell(square) {
+                Cell::White => 'w',
+                Cell::Black => 'b',
+                Cell::WhiteKing => 'W',
+                Cell::BlackKing => 'B',
+                Cell::Empty => 'e',
+            });
+        }
+        result
+    }
+
+    fn output_lines(output: &Arc<Mutex<Vec<u8>>>) -> Vec<String> {
+        String::from_utf8(output.lock().unwrap().clone()).unwrap().lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_quoted_arguments() {
+        let (command, args) = parse_line("pos pos=W moves=\"22-18 11-15\" infinite").unwrap();
+        assert_eq!(command, "pos");
+        assert_eq!(arg(&args, "moves"), Some("22-18 11-15"));
+        assert_eq!(arg(&args, "infinite"), Some(""));
+        assert_eq!(arg(&args, "depth"), None);
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let start = format_position(&Board::new());
+        assert_eq!(start, "Wbbbbbbbbbbbbeeeeeeeewwwwwwwwwwww");
+        assert_eq!(format_position(&parse_position(&start).unwrap()), start);
+
+        let pos = "BeeeebeeeeeeeeeeeeeeeeeeeeeeWeeee";
+        let board = parse_position(pos).unwrap();
+        assert_eq!(board.who_turn(), Side::Black);
+        assert_eq!(format_position(&board), pos);
+        assert!(parse_position("Wbbb").is_err());
+        assert!(parse_position("Xbbbbbbbbbbbbeeeeeeeewwwwwwwwwwww").is_err());
+    }
+
+    #[test]
+    fn moves_after_position() {
+        let mut board = Board::new();
+        assert_eq!(apply_moves(&mut board, "22-18 11-15"), Ok(()));
+        assert_eq!(format_position(&board), "Wbbbbbbbbbbebeebeeweewewwwwwwwwww");
+        assert!(apply_moves(&mut board, "22-18").is_err());
+    }
+
+    #[test]
+    fn think_and_answer() {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let mut engine = Engine::new(buffer.clone());
+        assert_eq!(engine.handle_line("init"), Ok(true));
+        assert_eq!(engine.handle_line("pos moves=\"22-18\""), Ok(true));
+        assert_eq!(engine.handle_line("level depth=3"), Ok(true));
+        assert_eq!(engine.handle_line("go think"), Ok(true));
+        engine.search.take().unwrap().join().unwrap();
+
+        let lines = output_lines(&buffer);
+        assert_eq!(lines[0], "ready");
+        assert!(lines.iter().any(|line| line.starts_with("info depth=3 ")));
+        let done = lines.last().unwrap();
+        let turn = done.strip_prefix("done move=").unwrap();
+        let mut board = engine.board;
+        assert_eq!(apply_moves(&mut board, turn), Ok(()));
+        assert_eq!(engine.handle_line("bogus"), Err(HubError::UnknownCommand("bogus".to_string())));
+    }
+
+    #[test]
+    fn bad_move_time() {
+        let mut engine = Engine::new(Arc::new(Mutex::new(vec![])));
+        assert_eq!(engine.handle_line("level move-time=inf"), Err(HubError::BadValue("inf".to_string())));
+        assert_eq!(engine.handle_line("level move-time=1e30"), Err(HubError::BadValue("1e30".to_string())));
+        assert_eq!(engine.handle_line("level move-time=1e18"), Ok(true));
+        assert_eq!(engine.level, Level::MoveTime(Duration::from_secs(1_000_000_000_000_000_000)));
+        assert_eq!(engine.handle_line("level move-time=-1"), Ok(true));
+        assert_eq!(engine.level, Level::MoveTime(Duration::ZERO));
+    }
+
+    #[test]
+    fn stop_infinite_search() {
+        let buffer = Arc::new(Mutex::new(vec![]));
+        let mut engine = Engine::new(buffer.clone());
+        assert_eq!(engine.handle_line("go analyze"), Ok(true));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(engine.handle_line("stop"), Ok(true));
+        assert!(output_lines(&buffer).last().unwrap().starts_with("done move="));
+        assert_eq!(engine.handle_line("quit"), Ok(false));
+    }
+}
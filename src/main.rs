@@ -1,10 +1,41 @@
+mod ai;
+mod assets;
 mod board;
+mod board_view;
+mod game;
+mod notation;
+mod pdn;
+mod review;
+mod theme;
 mod player_random;
 mod player_minmax;
 mod gui;
+mod cli;
+mod clock;
+mod editor;
+mod tui;
+mod network;
+mod hub;
+mod dxp;
+#[cfg(feature = "server")]
+mod server;
 
+use std::error::Error;
 
-fn main() -> eframe::Result<()> {
+const USAGE: &str = "usage: checkers [command]
+commands:
+  (none)  open the game window
+  play    play in the terminal, see `checkers play --help`
+  review  [game.pdn] find mistakes of a game, see `checkers review --help`
+  host    [address] open the game window and wait for the opponent from another window,
+          with --opponent the computer plays in the terminal, see `checkers host --help`
+  join    [address] open the game window and join the game hosted on address, --opponent like host
+  hub     run as an engine for external programs over stdin and stdout
+  dxp     play against another program over DamExchange protocol, see `checkers dxp --help`
+  serve   http api, needs `server` feature, see `checkers serve --help`";
+
+
+fn run_gui(app: impl FnOnce(&eframe::CreationContext) -> gui::App + 'static) -> eframe::Result<()> {
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([300.0, 320.0])
@@ -18,6 +49,35 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "checkers",
         native_options,
-        Box::new(|_cc| Box::new(gui::App::new())),
+        Box::new(move |cc| {
+            egui_extras::install_image_loaders(&cc.egui_ctx);
+            assets::install(&cc.egui_ctx);
+            Box::new(app(cc))
+        }),
     )
 }
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        None => run_gui(|cc| gui::App::resumed(cc.storage))?,
+        Some("host") | Some("join") if args.iter().skip(1).any(|arg| arg.starts_with("--") || arg == "-h") => network::run(&args)?,
+        Some("host") => {
+            let address = args.get(1).cloned().unwrap_or(network::DEFAULT_ADDRESS.to_string());
+            run_gui(move |_| gui::App::host(&address))?
+        },
+        Some("join") => {
+            let address = args.get(1).cloned().unwrap_or(network::DEFAULT_ADDRESS.to_string());
+            run_gui(move |_| gui::App::join(&address))?
+        },
+        Some("play") => cli::play(&args[1..])?,
+        Some("review") => cli::review(&args[1..])?,
+        Some("hub") => hub::run()?,
+        Some("dxp") => dxp::run(&args[1..])?,
+        #[cfg(feature = "server")]
+        Some("serve") => server::run(&args[1..])?,
+        Some("help") | Some("--help") | Some("-h") => println!("{}", USAGE),
+        Some(command) => return Err(format!("unknown command \"{}\"\n{}", command, USAGE).into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,498 @@
+//! game between two windows over tcp, one hosts the game and another joins it
+//!
+//! every message is one line of text, moves are sent ply by ply and checked by both sides.
+//! the host owns the game, on every connection it sends the whole game with `sync`,
+//! so after a lost connection the guest connects again and continues.
+//! with `--opponent` the computer plays the game in the terminal instead of a window
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::board::{Board, Move, MoveError, Side};
+use crate::cli::{self, ChouseMove};
+use crate::dxp;
+use crate::game::{Game, GameResult};
+use crate::notation;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:27532";
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+const NETWORK_USAGE: &str = "usage: checkers host [address] --opponent random|minmax5|minmax10|minmax15 [--side white|black]
+       checkers join [address] --opponent random|minmax5|minmax10|minmax15";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// whole game from the host, `side` is the side of the one who receives it
+    Sync {side: Side, result: GameResult, moves: Vec<Move>},
+    /// guest asks for `Sync`, when it doesn't agree with the host
+    Resync,
+    Move(Move),
+    Resign,
+    DrawOffer,
+    DrawAccept,
+    DrawDecline,
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::White => "white",
+        Side::Black => "black",
+    }
+}
+
+fn parse_side(s: &str) -> Option<Side> {
+    match s {
+        "white" => Some(Side::White),
+        "black" => Some(Side::Black),
+        _ => None,
+    }
+}
+
+fn parse_move(s: &str) -> Option<Move> {
+    match notation::parse_move(s).ok()?.as_slice() {
+        [mv] => Some(*mv),
+        _ => None,
+    }
+}
+
+impl Message {
+    /// whole `game` for the one who plays `side`
+    pub fn sync(game: &Game, side: Side) -> Message {
+        Message::Sync {side, result: game.result(), moves: game.line().iter().map(|ply| ply.mv).collect()}
+    }
+
+    pub fn parse(line: &str) -> Option<Message> {
+        let mut words = line.split_whitespace();
+        let message = match words.next()? {
+            "sync" => {
+                let side = parse_side(words.next()?)?;
+                let result = match words.next()? {
+                    "ongoing" => GameResult::Ongoing,
+                    "draw" => GameResult::Draw,
+                    winner => GameResult::Win(parse_side(winner)?),
+                };
+                let mut moves = vec![];
+                for word in words.by_ref() {
+                    moves.push(parse_move(word)?);
+                }
+                Message::Sync {side, result, moves}
+            },
+            "resync" => Message::Resync,
+            "move" => Message::Move(parse_move(words.next()?)?),
+            "resign" => Message::Resign,
+            "draw-offer" => Message::DrawOffer,
+            "draw-accept" => Message::DrawAccept,
+            "draw-decline" => Message::DrawDecline,
+            _ => return None,
+        };
+        match words.next() {
+            Some(_) => None,
+            None => Some(message),
+        }
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Message::Sync {side, result, moves} => {
+                let result = match result {
+                    GameResult::Ongoing => "ongoing",
+                    GameResult::Draw => "draw",
+                    GameResult::Win(side) => side_name(*side),
+                };
+                write!(f, "sync {} {}", side_name(*side), result)?;
+                for mv in moves {
+                    write!(f, " {}-{}", mv.from, mv.to)?;
+                }
+                Ok(())
+            },
+            Message::Resync => write!(f, "resync"),
+            Message::Move(mv) => write!(f, "move {}-{}", mv.from, mv.to),
+            Message::Resign => write!(f, "resign"),
+            Message::DrawOffer => write!(f, "draw-offer"),
+            Message::DrawAccept => write!(f, "draw-accept"),
+            Message::DrawDecline => write!(f, "draw-decline"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Connected,
+    Disconnected,
+    Message(Message),
+    /// text which is not a message, the connection is kept
+    BadMessage(String),
+    /// can't listen or connect at all, the peer is stopped
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    Host,
+    Guest,
+}
+
+/// other window of the game, the connection lives in its own thread
+pub struct Peer {
+    pub role: Role,
+    pub address: String,
+    pub connected: bool,
+    outgoing: Sender<Message>,
+    incoming: Receiver<Event>,
+    thread: JoinHandle<()>,
+}
+
+impl Peer {
+    /// wait for guests on `address`, one at a time
+    pub fn host(address: &str) -> Peer {
+        Peer::start(Role::Host, address)
+    }
+
+    /// connect to the host on `address`, connect again when the connection is lost
+    pub fn join(address: &str) -> Peer {
+        Peer::start(Role::Guest, address)
+    }
+
+    fn start(role: Role, address: &str) -> Peer {
+        let (outgoing, outgoing_receiver) = mpsc::channel();
+        let (incoming_sender, incoming) = mpsc::channel();
+        let thread_address = address.to_string();
+        let thread = thread::spawn(move || {
+            let result = match role {
+                Role::Host => run_host(&thread_address, &outgoing_receiver, &incoming_sender),
+                Role::Guest => run_guest(&thread_address, &outgoing_receiver, &incoming_sender),
+            };
+            if let Err(e) = result {
+                let _ = incoming_sender.send(Event::Failed(e.to_string()));
+            }
+        });
+        Peer {role, address: address.to_string(), connected: false, outgoing, incoming, thread}
+    }
+
+    pub fn send(&self, message: Message) {
+        let _ = self.outgoing.send(message);
+    }
+
+    /// next event if there is one, never blocks
+    pub fn poll(&mut self) -> Option<Event> {
+        let event = self.incoming.try_recv().ok()?;
+        match event {
+            Event::Connected => self.connected = true,
+            Event::Disconnected | Event::Failed(_) => self.connected = false,
+            _ => (),
+        }
+        Some(event)
+    }
+
+    /// stop after the messages which are already sent, the connection is closed when it returns
+    pub fn close(self) {
+        drop(self.outgoing);
+        let _ = self.thread.join();
+    }
+}
+
+/// game of a `Sync` message, every move is checked like a move of the opponent
+pub fn synced_game(result: GameResult, moves: &[Move]) -> Result<Game, MoveError> {
+    let mut game = Game::new();
+    for &mv in moves {
+        game.do_move(mv)?;
+    }
+    match result {
+        GameResult::Draw if !game.board().is_ended() => game.agree_draw(),
+        GameResult::Win(_) if !game.board().is_ended() => game.set_result(result),
+        _ => (),
+    }
+    Ok(game)
+}
+
+enum Exit {
+    /// the connection is lost
+    Disconnected,
+    /// the peer is dropped
+    Stopped,
+}
+
+/// pass messages both ways until one of the ends is gone
+fn serve_connection(stream: TcpStream, outgoing: &Receiver<Message>, incoming: &Sender<Event>) -> io::Result<Exit> {
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    // messages of the previous connection are outdated, the host sends sync anyway
+    while outgoing.try_recv().is_ok() {}
+    if incoming.send(Event::Connected).is_err() {
+        return Ok(Exit::Stopped);
+    }
+
+    let mut line = String::new();
+    loop {
+        loop {
+            match outgoing.try_recv() {
+                Ok(message) => {
+                    if writeln!(writer, "{}", message).is_err() {
+                        return Ok(Exit::Disconnected);
+                    }
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(Exit::Stopped),
+            }
+        }
+
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ok(Exit::Disconnected),
+            Ok(_) => {
+                let event = match Message::parse(&line) {
+                    Some(message) => Event::Message(message),
+                    None => Event::BadMessage(line.trim().to_string()),
+                };
+                line.clear();
+                if incoming.send(event).is_err() {
+                    return Ok(Exit::Stopped);
+                }
+            },
+            // a part of the line can be already read, it stays in `line`
+            Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => (),
+            Err(_) => return Ok(Exit::Disconnected),
+        }
+    }
+}
+
+/// true if the peer is dropped and the thread must end
+fn wait(interval: Duration, outgoing: &Receiver<Message>) -> bool {
+    thread::sleep(interval);
+    loop {
+        match outgoing.try_recv() {
+            Ok(_) => (),
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => return true,
+        }
+    }
+}
+
+fn run_host(address: &str, outgoing: &Receiver<Message>, incoming: &Sender<Event>) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    listener.set_nonblocking(true)?;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                match serve_connection(stream, outgoing, incoming)? {
+                    Exit::Disconnected => {
+                        if incoming.send(Event::Disconnected).is_err() {
+                            return Ok(());
+                        }
+                    },
+                    Exit::Stopped => return Ok(()),
+                }
+            },
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if wait(POLL_INTERVAL, outgoing) {
+                    return Ok(());
+                }
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn run_guest(address: &str, outgoing: &Receiver<Message>, incoming: &Sender<Event>) -> io::Result<()> {
+    loop {
+        if let Ok(stream) = TcpStream::connect(address) {
+            match serve_connection(stream, outgoing, incoming)? {
+                Exit::Disconnected => {
+                    if incoming.send(Event::Disconnected).is_err() {
+                        return Ok(());
+                    }
+                },
+                Exit::Stopped => return Ok(()),
+            }
+        }
+        if wait(RECONNECT_INTERVAL, outgoing) {
+            return Ok(());
+        }
+    }
+}
+
+/// computer plays one game with `peer`, the guest gets its side from the host instead of `side`
+pub fn play(mut peer: Peer, mut side: Side, chouse_func: ChouseMove) -> Result<GameResult, Box<dyn Error>> {
+    let mut game = Game::new();
+    // the guest doesn't know the game until the host sends it
+    let mut synced = peer.role == Role::Host;
+    // position before the turn of the opponent and its moves so far
+    let mut opponent_turn: (Board, Vec<Move>) = (*game.board(), vec![]);
+    loop {
+        let ongoing = game.result() == GameResult::Ongoing;
+        if ongoing && synced && peer.connected && game.board().who_turn() == side {
+            let before = *game.board();
+            let mvs = cli::play_turn(&mut game, chouse_func)?;
+            for &mv in mvs.iter() {
+                peer.send(Message::Move(mv));
+            }
+            println!("{} played {}", side, notation::format_turn(&before, &mvs));
+            continue;
+        }
+        // the host waits until the guest has the end of the game and goes away
+        if !ongoing && (peer.role == Role::Guest || !peer.connected) {
+            peer.close();
+            return Ok(game.result());
+        }
+
+        let event = match peer.poll() {
+            Some(event) => event,
+            None => {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            },
+        };
+        match event {
+            Event::Connected if peer.role == Role::Host => peer.send(Message::sync(&game, !side)),
+            Event::Connected => (),
+            Event::Disconnected => {
+                println!("disconnected");
+                synced = peer.role == Role::Host;
+            },
+            Event::Message(Message::Sync {side: synced_side, result, moves}) if peer.role == Role::Guest => {
+                game = synced_game(result, &moves).map_err(|e| format!("host sent an unavailable move: {}", e))?;
+                side = synced_side;
+                synced = true;
+                println!("playing {} from move {}", side, game.ply());
+            },
+            Event::Message(Message::Move(mv)) => {
+                let before = *game.board();
+                let wrong = match game.board().who_turn() == side || !ongoing {
+                    true => Some(format!("opponent moved out of turn: {}-{}", mv.from, mv.to)),
+                    false => game.do_move(mv).err().map(|e| format!("opponent made an unavailable move: {}", e)),
+                };
+                if let Some(text) = wrong {
+                    // the host's game wins
+                    println!("{}", text);
+                    match peer.role {
+                        Role::Host => peer.send(Message::sync(&game, !side)),
+                        Role::Guest => peer.send(Message::Resync),
+                    }
+                    continue;
+                }
+                if before.jumping_piece().is_none() {
+                    opponent_turn = (before, vec![]);
+                }
+                opponent_turn.1.push(mv);
+                if game.board().jumping_piece().is_none() {
+                    println!("{} played {}", !side, notation::format_turn(&opponent_turn.0, &opponent_turn.1));
+                }
+            },
+            Event::Message(Message::Resync) if peer.role == Role::Host => peer.send(Message::sync(&game, !side)),
+            Event::Message(Message::Resign) => {
+                game.set_result(GameResult::Win(side));
+                println!("opponent resigned");
+            },
+            // the computer plays on
+            Event::Message(Message::DrawOffer) => peer.send(Message::DrawDecline),
+            Event::Message(Message::DrawAccept) => game.agree_draw(),
+            Event::Message(message) => println!("unexpected message: {}", message),
+            Event::BadMessage(text) => println!("unknown message: {}", text),
+            Event::Failed(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// `checkers host` and `checkers join` with a computer player instead of a window
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", NETWORK_USAGE);
+        return Ok(());
+    }
+    let address = match args.get(1) {
+        Some(arg) if !arg.starts_with("--") => arg.as_str(),
+        _ => DEFAULT_ADDRESS,
+    };
+    let chouse_func = match cli::parse_opponent(cli::option_value(args, "--opponent")?.unwrap_or("none"))? {
+        Some(chouse_func) => chouse_func,
+        None => return Err("a game without a window needs a computer player".into()),
+    };
+    let side = cli::parse_side(cli::option_value(args, "--side")?.unwrap_or("white"))?;
+    let peer = match args.first().map(|arg| arg.as_str()) {
+        Some("host") => Peer::host(address),
+        Some("join") => Peer::join(address),
+        _ => return Err(NETWORK_USAGE.into()),
+    };
+    let result = play(peer, side, chouse_func)?;
+    println!("result: {}", dxp::result_text(result));
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Square;
+    use std::time::Instant;
+
+    fn mv(from: usize, to: usize) -> Move {
+        Move::new(Square::from_number(from).unwrap(), Square::from_number(to).unwrap())
+    }
+
+    fn next_event(peer: &mut Peer) -> Event {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if let Some(event) = peer.poll() {
+                return event;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("no event");
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = vec![
+            Message::Sync {side: Side::Black, result: GameResult::Ongoing, moves: vec![mv(22, 18), mv(11, 15)]},
+            Message::Sync {side: Side::White, result: GameResult::Win(Side::Black), moves: vec![]},
+            Message::Resync,
+            Message::Move(mv(22, 18)),
+            Message::Resign,
+            Message::DrawOffer,
+            Message::DrawAccept,
+            Message::DrawDecline,
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.to_string()), Some(message));
+        }
+        assert_eq!(Message::parse("move 22-18x15"), None);
+        assert_eq!(Message::parse("resign now"), None);
+        assert_eq!(Message::parse("hello"), None);
+    }
+
+    #[test]
+    fn host_and_guest_talk_and_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        let mut host = Peer::host(&address);
+        let mut guest = Peer::join(&address);
+        assert_eq!(next_event(&mut host), Event::Connected);
+        assert_eq!(next_event(&mut guest), Event::Connected);
+
+        guest.send(Message::Move(mv(22, 18)));
+        assert_eq!(next_event(&mut host), Event::Message(Message::Move(mv(22, 18))));
+        host.send(Message::DrawOffer);
+        assert_eq!(next_event(&mut guest), Event::Message(Message::DrawOffer));
+
+        // the guest window is closed and opened again
+        drop(guest);
+        assert_eq!(next_event(&mut host), Event::Disconnected);
+        assert!(!host.connected);
+        let mut guest = Peer::join(&address);
+        assert_eq!(next_event(&mut host), Event::Connected);
+        assert_eq!(next_event(&mut guest), Event::Connected);
+        host.send(Message::Resign);
+        assert_eq!(next_event(&mut guest), Event::Message(Message::Resign));
+    }
+}
@@ -0,0 +1,314 @@
+use std::error;
+use std::fmt;
+
+use crate::board::{Board, BoardBuilder, Cell, Move, SetupError, Side, Square, SquareError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotationError {
+    /// text which is neither square number nor algebraic square like "c3"
+    UnknownSquare(String),
+    Square(SquareError),
+    /// move needs at least two squares
+    TooShort,
+    BadFen(String),
+    Setup(SetupError),
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotationError::UnknownSquare(s) => write!(f, "unknown square \"{}\"", s),
+            NotationError::Square(e) => write!(f, "{}", e),
+            NotationError::TooShort => write!(f, "move needs at least two squares"),
+            NotationError::BadFen(s) => write!(f, "bad fen \"{}\"", s),
+            NotationError::Setup(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for NotationError {}
+
+impl From<SquareError> for NotationError {
+    fn from(e: SquareError) -> Self {
+        NotationError::Square(e)
+    }
+}
+
+impl From<SetupError> for NotationError {
+    fn from(e: SetupError) -> Self {
+        NotationError::Setup(e)
+    }
+}
+
+/// square number like "22" or algebraic name like "c3"
+pub fn parse_square(s: &str) -> Result<Square, NotationError> {
+    let s = s.trim();
+    if let Ok(number) = s.parse::<usize>() {
+        return Ok(Square::from_number(number)?);
+    }
+
+    let mut chars = s.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => {
+            let x = file as usize - 'a' as usize;
+            let y = 8 - (rank as usize - '0' as usize);
+            Ok(Square::new(x, y)?)
+        },
+        _ => Err(NotationError::UnknownSquare(s.to_string())),
+    }
+}
+
+/// squares separated by '-', 'x' or ':', like "22-18", "22x15x8" or "c3-d4"
+///
+/// return one move for every jump of a multi-jump
+pub fn parse_move(s: &str) -> Result<Vec<Move>, NotationError> {
+    let mut squares = vec![];
+    for part in s.trim().split(['-', 'x', ':']) {
+        squares.push(parse_square(part)?);
+    }
+    if squares.len() < 2 {
+        return Err(NotationError::TooShort);
+    }
+    Ok(squares.windows(2).map(|w| Move::new(w[0], w[1])).collect())
+}
+
+pub fn algebraic(square: Square) -> String {
+    format!("{}{}", (b'a' + square.x() as u8) as char, 8 - square.y())
+}
+
+/// square of the piece between squares of the move
+pub fn captured_square(board: &Board, mv: Move) -> Option<Square> {
+    let dir_x: i32 = if mv.to.x() > mv.from.x() { 1 } else { -1 };
+    let dir_y: i32 = if mv.to.y() > mv.from.y() { 1 } else { -1 };
+    let mut x = mv.from.x() as i32 + dir_x;
+    let mut y = mv.from.y() as i32 + dir_y;
+    while x != mv.to.x() as i32 && y != mv.to.y() as i32 {
+        if let Ok(square) = Square::new(x as usize, y as usize) {
+            if board.get_cell(square) != Cell::Empty {
+                return Some(square);
+            }
+        }
+        x += dir_x;
+        y += dir_y;
+    }
+    None
+}
+
+/// there is a piece between squares of the move
+pub fn is_capture(board: &Board, mv: Move) -> bool {
+    captured_square(board, mv).is_some()
+}
+
+/// squares of all pieces taken during a turn, `board` is the position before the first move
+pub fn captured_squares(board: &Board, mvs: &[Move]) -> Vec<Square> {
+    let mut board = *board;
+    let mut result = vec![];
+    for &mv in mvs {
+        if let Some(square) = captured_square(&board, mv) {
+            result.push(square);
+        }
+        board.do_move_without_checks(mv);
+    }
+    result
+}
+
+/// every way to play a whole turn, a multi-jump is one turn of several moves
+pub fn complete_turns(board: &Board) -> Vec<Vec<Move>> {
+    let side = board.who_turn();
+    let mut result = vec![];
+    for mv in board.clone().all_available_moves() {
+        let mut next = *board;
+        next.do_move_without_checks(mv);
+        if next.who_turn() == side && !next.is_ended() {
+            for mut rest in complete_turns(&next) {
+                rest.insert(0, mv);
+                result.push(rest);
+            }
+        } else {
+            result.push(vec![mv]);
+        }
+    }
+    result
+}
+
+/// moves grouped by turns like "22-18 11-15 18x11", a multi-jump is one turn
+pub fn format_line(board: &Board, mvs: &[Move]) -> String {
+    let mut board = *board;
+    let mut turns = vec![];
+    let mut turn_board = board;
+    let mut turn = vec![];
+    for &mv in mvs {
+        if board.jumping_piece().is_none() && !turn.is_empty() {
+            turns.push(format_turn(&turn_board, &turn));
+            turn_board = board;
+            turn.clear();
+        }
+        turn.push(mv);
+        board.do_move_without_checks(mv);
+    }
+    if !turn.is_empty() {
+        turns.push(format_turn(&turn_board, &turn));
+    }
+    turns.join(" ")
+}
+
+/// score of the engine like "+2", wins are shown as words
+pub fn format_score(score: i8) -> String {
+    match score {
+        100 => "white wins".to_string(),
+        -100 => "black wins".to_string(),
+        score => format!("{:+}", score),
+    }
+}
+
+/// numeric notation of a whole turn, `board` is the position before the first move
+pub fn format_turn(board: &Board, mvs: &[Move]) -> String {
+    let mut result = String::new();
+    if let Some(first) = mvs.first() {
+        let separator = if is_capture(board, *first) { "x" } else { "-" };
+        result.push_str(&first.from.to_string());
+        for mv in mvs {
+            result.push_str(separator);
+            result.push_str(&mv.to.to_string());
+        }
+    }
+    result
+}
+
+/// pdn fen like "W:W21,22,K30:B1-12", side to move and then pieces of both sides, `K` marks kings
+pub fn parse_fen(s: &str) -> Result<Board, NotationError> {
+    let bad = || NotationError::BadFen(s.to_string());
+    let mut parts = s.trim().trim_end_matches('.').split(':');
+    let mut builder = match parts.next().map(|part| part.trim()) {
+        Some("W") => BoardBuilder::new().side(Side::White),
+        Some("B") => BoardBuilder::new().side(Side::Black),
+        _ => return Err(bad()),
+    };
+
+    for part in parts {
+        let part = part.trim();
+        let (man, king) = match part.chars().next() {
+            Some('W') => (Cell::White, Cell::WhiteKing),
+            Some('B') => (Cell::Black, Cell::BlackKing),
+            _ => return Err(bad()),
+        };
+        for piece in part[1..].split(',').map(|piece| piece.trim()).filter(|piece| !piece.is_empty()) {
+            let (cell, squares) = match piece.strip_prefix('K') {
+                Some(squares) => (king, squares),
+                None => (man, piece),
+            };
+            let (first, last) = match squares.split_once('-') {
+                Some((first, last)) => (first, last),
+                None => (squares, squares),
+            };
+            let first: usize = first.parse().map_err(|_| bad())?;
+            let last: usize = last.parse().map_err(|_| bad())?;
+            for number in first..=last {
+                builder = builder.piece(Square::from_number(number)?, cell);
+            }
+        }
+    }
+    Ok(builder.build()?)
+}
+
+pub fn format_fen(board: &Board) -> String {
+    let mut white = vec![];
+    let mut black = vec![];
+    for number in 1..=32 {
+        let square = Square::from_number(number).unwrap();
+        match board.get_cell(square) {
+            Cell::White => white.push(number.to_string()),
+            Cell::WhiteKing => white.push(format!("K{}", number)),
+            Cell::Black => black.push(number.to_string()),
+            Cell::BlackKing => black.push(format!("K{}", number)),
+            Cell::Empty => (),
+        }
+    }
+    let side = match board.who_turn() {
+        Side::White => "W",
+        Side::Black => "B",
+    };
+    format!("{}:W{}:B{}", side, white.join(","), black.join(","))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sq(number: usize) -> Square {
+        Square::from_number(number).unwrap()
+    }
+
+    #[test]
+    fn parse_numeric_square() {
+        assert_eq!(parse_square("22"), Ok(sq(22)));
+        assert_eq!(parse_square("33"), Err(NotationError::Square(SquareError::OutOfBounds)));
+    }
+
+    #[test]
+    fn parse_algebraic_square() {
+        assert_eq!(parse_square("c3"), Ok(sq(22)));
+        assert_eq!(parse_square("a1"), Ok(sq(29)));
+        assert_eq!(parse_square("h8"), Ok(sq(4)));
+        assert_eq!(parse_square("a8"), Err(NotationError::Square(SquareError::NotPlayableCell)));
+        assert_eq!(parse_square("z9"), Err(NotationError::UnknownSquare("z9".to_string())));
+    }
+
+    #[test]
+    fn parse_moves() {
+        assert_eq!(parse_move("22-18"), Ok(vec![Move::new(sq(22), sq(18))]));
+        assert_eq!(parse_move("c3-d4"), Ok(vec![Move::new(sq(22), sq(18))]));
+        assert_eq!(parse_move("22x15x8"), Ok(vec![Move::new(sq(22), sq(15)), Move::new(sq(15), sq(8))]));
+        assert_eq!(parse_move("22"), Err(NotationError::TooShort));
+    }
+
+    #[test]
+    fn format_moves() {
+        let board = Board::new();
+        assert_eq!(algebraic(sq(22)), "c3");
+        assert_eq!(format_turn(&board, &[Move::new(sq(22), sq(18))]), "22-18");
+    }
+
+    #[test]
+    fn fen() {
+        let start = "W:W21,22,23,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,11,12";
+        assert_eq!(format_fen(&Board::new()), start);
+        assert_eq!(format_fen(&parse_fen("W:W21-32:B1-12").unwrap()), start);
+
+        let board = parse_fen("B:WK30,18:B5").unwrap();
+        assert_eq!(board.get_cell(sq(30)), Cell::WhiteKing);
+        assert_eq!(board.who_turn(), Side::Black);
+        assert_eq!(format_fen(&board), "B:W18,K30:B5");
+
+        assert_eq!(parse_fen("X:W1").err(), Some(NotationError::BadFen("X:W1".to_string())));
+        assert_eq!(parse_fen("W:W1:B2").err(), Some(NotationError::Setup(SetupError::ManOnPromotionRow(sq(1)))));
+        assert_eq!(parse_fen("W:W33").err(), Some(NotationError::Square(SquareError::OutOfBounds)));
+    }
+
+    #[test]
+    fn multi_jump_turns() {
+        let board = Board::from_arr(Side::White, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', 'b'],
+            [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        let turn = vec![Move::new(sq(22), sq(15)), Move::new(sq(15), sq(6))];
+        assert_eq!(complete_turns(&board), vec![turn.clone()]);
+        assert_eq!(captured_squares(&board, &turn), vec![sq(18), sq(10)]);
+        assert_eq!(format_turn(&board, &turn), "22x15x6");
+    }
+
+    #[test]
+    fn moves_by_turns() {
+        let board = parse_fen("W:W22,K31:B18,10,1").unwrap();
+        let mvs = [Move::new(sq(22), sq(15)), Move::new(sq(15), sq(6)), Move::new(sq(1), sq(10))];
+        assert_eq!(format_line(&board, &mvs), "22x15x6 1x10");
+        assert_eq!(format_line(&board, &[]), "");
+    }
+}
@@ -0,0 +1,280 @@
+//! portable draughts notation, text format of games
+//!
+//! tags like `[Event "club game"]` followed by numbered turns like `1. 22-18 11-15 2. 18x11 8x15 2-0`
+
+use std::error;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::{Board, Move, Side};
+use crate::game::{Game, GameResult};
+use crate::notation::{self, NotationError};
+
+/// russian draughts in the list of pdn game types
+const GAME_TYPE: &str = "25";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdnError {
+    /// tag without closing bracket or quotes
+    BadTag(String),
+    Fen(NotationError),
+    /// move which can't be read or can't be played in the position
+    BadMove(String),
+}
+
+impl fmt::Display for PdnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PdnError::BadTag(s) => write!(f, "bad tag \"{}\"", s),
+            PdnError::Fen(e) => write!(f, "bad start position: {}", e),
+            PdnError::BadMove(s) => write!(f, "bad move \"{}\"", s),
+        }
+    }
+}
+
+impl error::Error for PdnError {}
+
+fn result_text(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Win(Side::White) => "2-0",
+        GameResult::Win(Side::Black) => "0-2",
+        GameResult::Draw => "1-1",
+        GameResult::Ongoing => "*",
+    }
+}
+
+/// results of russian and english checkers
+fn parse_result(s: &str) -> Option<GameResult> {
+    match s {
+        "2-0" | "1-0" => Some(GameResult::Win(Side::White)),
+        "0-2" | "0-1" => Some(GameResult::Win(Side::Black)),
+        "1-1" | "1/2-1/2" => Some(GameResult::Draw),
+        "*" => Some(GameResult::Ongoing),
+        _ => None,
+    }
+}
+
+/// date tag like "2026.10.19", the day is taken in UTC
+pub fn format_date(time: SystemTime) -> String {
+    let days = time.duration_since(UNIX_EPOCH).map(|since| since.as_secs() / 86400).unwrap_or(0);
+    // civil date from days, the year starts in march so the leap day is the last one
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let (year, month) = match month {
+        0..=9 => (era * 400 + year_of_era, month + 3),
+        _ => (era * 400 + year_of_era + 1, month - 9),
+    };
+    format!("{}.{:02}.{:02}", year, month, day)
+}
+
+/// tags and moves of the current line of the game
+pub fn write(game: &Game) -> String {
+    let mut result = String::new();
+    for (name, value) in game.tags.iter() {
+        if name != "Result" && name != "GameType" && name != "FEN" {
+            result.push_str(&format!("[{} \"{}\"]\n", name, value.replace('"', "'")));
+        }
+    }
+    result.push_str(&format!("[Result \"{}\"]\n", result_text(game.result())));
+    result.push_str(&format!("[GameType \"{}\"]\n", GAME_TYPE));
+    let start_fen = notation::format_fen(game.start_board());
+    if start_fen != notation::format_fen(&Board::new()) {
+        result.push_str(&format!("[FEN \"{}\"]\n", start_fen));
+    }
+    result.push('\n');
+
+    let mut words = vec![];
+    let mut number = 1;
+    for (i, turn) in game.turns().iter().enumerate() {
+        match turn.side {
+            Side::White => words.push(format!("{}.", number)),
+            Side::Black if i == 0 => words.push(format!("{}...", number)),
+            Side::Black => (),
+        }
+        words.push(notation::format_turn(&turn.board, &turn.mvs));
+        if turn.side == Side::Black {
+            number += 1;
+        }
+    }
+    words.push(result_text(game.result()).to_string());
+
+    // lines of 80 chars at most, like other pdn writers
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.len() + 1 + word.len() > 80 {
+            result.push_str(&line);
+            result.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    result.push_str(&line);
+    result.push('\n');
+    result
+}
+
+/// available turn which goes through squares of `text`, like "22x8" or "22x15x8"
+fn find_turn(board: &Board, text: &str) -> Option<Vec<Move>> {
+    let squares = notation::parse_move(text).ok()?;
+    let from = squares.first()?.from;
+    let to = squares.last()?.to;
+    let between: Vec<_> = squares.iter().skip(1).map(|mv| mv.from).collect();
+    notation::complete_turns(board).into_iter().find(|mvs| {
+        let hops: Vec<_> = mvs.iter().map(|mv| mv.to).collect();
+        let mut hops_left = hops.iter();
+        mvs[0].from == from
+            && mvs[mvs.len() - 1].to == to
+            && between.iter().all(|square| hops_left.any(|hop| hop == square))
+    })
+}
+
+/// remove comments `{...}` and variations `(...)`, they are not kept in the game
+fn main_line(movetext: &str) -> String {
+    let mut result = String::new();
+    let mut in_comment = false;
+    let mut variation_depth = 0;
+    for c in movetext.chars() {
+        match c {
+            '{' => in_comment = true,
+            '}' => in_comment = false,
+            '(' if !in_comment => variation_depth += 1,
+            ')' if !in_comment && variation_depth > 0 => variation_depth -= 1,
+            c if !in_comment && variation_depth == 0 => result.push(c),
+            _ => (),
+        }
+        if c == '}' || c == ')' {
+            result.push(' ');
+        }
+    }
+    result
+}
+
+/// first game of the text
+pub fn read(text: &str) -> Result<Game, PdnError> {
+    let mut tags = vec![];
+    let mut movetext = String::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') && movetext.trim().is_empty() {
+            let bad = || PdnError::BadTag(line.to_string());
+            let inner = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).ok_or_else(bad)?;
+            let (name, value) = inner.split_once(' ').ok_or_else(bad)?;
+            let value = value.trim().strip_prefix('"').and_then(|v| v.strip_suffix('"')).ok_or_else(bad)?;
+            tags.push((name.to_string(), value.to_string()));
+        } else if line.starts_with('[') {
+            // tags of the next game
+            break;
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => notation::parse_fen(fen).map_err(PdnError::Fen)?,
+        None => Board::new(),
+    };
+    let mut game = Game::from_board(start);
+    let mut result = tags.iter().find(|(name, _)| name == "Result").and_then(|(_, value)| parse_result(value));
+    game.tags = tags.into_iter().filter(|(name, _)| name != "Result" && name != "GameType" && name != "FEN").collect();
+
+    for word in main_line(&movetext).split_whitespace() {
+        if let Some(word_result) = parse_result(word) {
+            result = Some(word_result);
+            break;
+        }
+        // move numbers like "12." or "12...", annotations like "!?" and "$2"
+        let word = match word.split_once('.') {
+            Some((number, rest)) if number.chars().all(|c| c.is_ascii_digit()) => rest.trim_start_matches('.'),
+            _ => word,
+        };
+        let word = word.trim_end_matches(['!', '?']);
+        if word.is_empty() || word.starts_with('$') {
+            continue;
+        }
+        let mvs = find_turn(game.board(), word).ok_or_else(|| PdnError::BadMove(word.to_string()))?;
+        for mv in mvs {
+            game.do_move(mv).map_err(|_| PdnError::BadMove(word.to_string()))?;
+        }
+    }
+
+    match result {
+        Some(GameResult::Ongoing) | None => (),
+        Some(GameResult::Draw) if !game.board().is_ended() => game.agree_draw(),
+        Some(result) => game.set_result(result),
+    }
+    Ok(game)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Square;
+
+    fn mv(from: usize, to: usize) -> Move {
+        Move::new(Square::from_number(from).unwrap(), Square::from_number(to).unwrap())
+    }
+
+    #[test]
+    fn write_and_read() {
+        let mut game = Game::new();
+        game.set_tag("Event", "test");
+        for (from, to) in [(22, 18), (11, 15), (18, 11), (8, 15)] {
+            game.do_move(mv(from, to)).unwrap();
+        }
+        let text = write(&game);
+        assert_eq!(text, "[Event \"test\"]\n[Result \"*\"]\n[GameType \"25\"]\n\n1. 22-18 11-15 2. 18x11 8x15 *\n");
+
+        let read_game = read(&text).unwrap();
+        assert_eq!(read_game.tag("Event"), Some("test"));
+        assert_eq!(read_game.history().iter().map(|ply| ply.mv).collect::<Vec<_>>(), game.history().iter().map(|ply| ply.mv).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn dates() {
+        let date = |seconds| format_date(UNIX_EPOCH + std::time::Duration::from_secs(seconds));
+        assert_eq!(date(0), "1970.01.01");
+        assert_eq!(date(951_782_400), "2000.02.29");
+        assert_eq!(date(1_792_368_000 + 86_399), "2026.10.19");
+    }
+
+    #[test]
+    fn read_comments_variations_and_short_captures() {
+        let text = "[Event \"x\"]\n[Result \"0-2\"]\n\n1. 22-18 {good} 11-15 (1... 12-16 2. 18-14) 2. 18x11! 8x15 0-2\n";
+        let game = read(text).unwrap();
+        assert_eq!(game.ply(), 4);
+        assert_eq!(game.result(), GameResult::Win(Side::Black));
+    }
+
+    #[test]
+    fn position_and_black_first() {
+        let mut game = Game::from_board(notation::parse_fen("B:W30:B10,1").unwrap());
+        game.do_move(mv(10, 14)).unwrap();
+        let text = write(&game);
+        assert!(text.contains("[FEN \"B:W30:B1,10\"]"));
+        assert!(text.contains("1... 10-14"));
+        assert_eq!(read(&text).unwrap().ply(), 1);
+    }
+
+    #[test]
+    fn multi_jump_by_ends() {
+        let game = read("[FEN \"W:W22:B18,10,1\"]\n1. 22x6 *").unwrap();
+        assert_eq!(game.ply(), 2);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(read("[Event x]").err(), Some(PdnError::BadTag("[Event x]".to_string())));
+        assert_eq!(read("1. 22-13").err(), Some(PdnError::BadMove("22-13".to_string())));
+        assert!(matches!(read("[FEN \"W:W33\"]").err(), Some(PdnError::Fen(_))));
+    }
+}
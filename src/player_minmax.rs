@@ -1,4 +1,7 @@
-use crate::board::{Board, Move, Side, Cell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::board::{Board, Move, Side, Cell, State};
 
 
 #[derive(Debug)]
@@ -7,23 +10,58 @@ struct ScoredMove {
     score: i8,
 }
 
-/// positive numbers is good position, negative numbers is bad position
+/// result of one finished iteration of `search`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
+pub struct SearchInfo {
+    pub depth: usize,
+    pub mv: Option<Move>,
+    /// positive numbers is good for white, negative numbers is good for black
+    pub score: i8,
+    pub nodes: u64,
+}
+
+/// limits of a running search
+struct Search<'a> {
+    stop: &'a AtomicBool,
+    deadline: Option<Instant>,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl Search<'_> {
+    /// checks the flag and the clock only once in a while, they are slow compared to a node
+    fn should_stop(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.aborted && self.nodes.is_multiple_of(1024) {
+            let out_of_time = match self.deadline {
+                Some(deadline) => Instant::now() >= deadline,
+                None => false,
+            };
+            self.aborted = out_of_time || self.stop.load(Ordering::Relaxed);
+        }
+        self.aborted
+    }
+}
+
+/// positive numbers is good for white, negative numbers is good for black
 #[inline(always)]
 fn count_score(board: &Board) -> i8 {
-    let who_turn = board.who_turn();
+    if let State::Draw(_) = board.state() {
+        return 0;
+    }
 
     match board.who_win() {
-        Some(x) if x == who_turn => 100,
-        Some(x) if x != who_turn => -100,
-        None => (board.count(Cell::White) - board.count(Cell::Black)) as i8,
-        Some(_) => unreachable!(),
+        Some(Side::White) => 100,
+        Some(Side::Black) => -100,
+        None => board.count(Cell::White) as i8 - board.count(Cell::Black) as i8,
     }
 }
 
 
-fn compute_best_move(board: &mut Board, depth: usize, incoming_alpha: i8, incoming_beta: i8, play_as_white: bool) -> ScoredMove {
-    let board_score = count_score(&board);
-    if depth == 0 {
+fn compute_best_move(board: &mut Board, depth: usize, incoming_alpha: i8, incoming_beta: i8, play_as_white: bool, search: &mut Search) -> ScoredMove {
+    let board_score = count_score(board);
+    if search.should_stop() || depth == 0 {
         return ScoredMove {mv: None, score: board_score};
     }
 
@@ -45,16 +83,15 @@ fn compute_best_move(board: &mut Board, depth: usize, incoming_alpha: i8, incomi
     let mut alpha = incoming_alpha;
     let mut beta = incoming_beta;
         
-    for i in 0..mvs_amount {
-        let mv = mvs[i];
-        let mut test_board = board.clone();
+    for &mv in &mvs {
+        let mut test_board = *board;
         test_board.do_move_without_checks(mv);
 
         let next_best_move;
         if test_board.who_turn() == board.who_turn() {
-            next_best_move = compute_best_move(&mut test_board, depth - 1, alpha, beta, play_as_white);
+            next_best_move = compute_best_move(&mut test_board, depth - 1, alpha, beta, play_as_white, search);
         } else {
-            next_best_move = compute_best_move(&mut test_board, depth - 1, alpha, beta, !play_as_white);
+            next_best_move = compute_best_move(&mut test_board, depth - 1, alpha, beta, !play_as_white, search);
         
             if play_as_white {
                 if next_best_move.score > alpha {
@@ -77,26 +114,245 @@ fn compute_best_move(board: &mut Board, depth: usize, incoming_alpha: i8, incomi
         }
     }
 
-    return best_mv;
+    best_mv
 }
 
 
+fn search_depth(board: &Board, depth: usize, search: &mut Search) -> ScoredMove {
+    let mut board = *board;
+    let play_as_white = board.who_turn() == Side::White;
+    compute_best_move(&mut board, depth, -127, 127, play_as_white, search)
+}
+
 /// dir can be -1 or 1 it is for best or words move chousing (1 for best, -1 for worst)
 pub fn best_move(board: &mut Board, depth: usize) -> Option<Move> {
+    let stop = AtomicBool::new(false);
+    let mut search = Search {stop: &stop, deadline: None, nodes: 0, aborted: false};
+    search_depth(board, depth, &mut search).mv
+}
+
+/// iterative deepening up to `max_depth` until `deadline` or until `stop` is set
+///
+/// `on_depth` is called after every finished depth, an interrupted depth is thrown away
+/// so the result is the one of the deepest finished depth
+#[allow(dead_code)]
+pub fn search(board: &Board, max_depth: usize, deadline: Option<Instant>, stop: &AtomicBool, mut on_depth: impl FnMut(&SearchInfo)) -> Option<SearchInfo> {
+    let mut search = Search {stop, deadline, nodes: 0, aborted: false};
+    let mut result = None;
+    for depth in 1..=max_depth {
+        let scored = search_depth(board, depth, &mut search);
+        if search.aborted {
+            break;
+        }
+        let info = SearchInfo {depth, mv: scored.mv, score: scored.score, nodes: search.nodes};
+        on_depth(&info);
+        result = Some(info);
+        if scored.mv.is_none() || scored.score.abs() == 100 {
+            break;
+        }
+    }
+    result
+}
+
+/// candidate move of `analyze` and moves which are expected after it
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub struct Line {
+    /// positive numbers is good for white, negative numbers is good for black
+    pub score: i8,
+    /// principal variation, it starts with the candidate move
+    pub mvs: Vec<Move>,
+}
+
+/// result of one finished iteration of `analyze`
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+pub struct Analysis {
+    pub depth: usize,
+    pub nodes: u64,
+    /// best line first
+    pub lines: Vec<Line>,
+}
+
+/// every available move with the score of `depth` plies, best move for the side to move first
+#[allow(dead_code)]
+fn scored_moves(board: &Board, depth: usize, search: &mut Search) -> Option<Vec<(Move, i8)>> {
+    let mut scored = vec![];
+    for mv in board.clone().all_available_moves() {
+        let mut next = *board;
+        next.do_move_without_checks(mv);
+        let score = search_depth(&next, depth.max(1) - 1, search).score;
+        if search.aborted {
+            return None;
+        }
+        scored.push((mv, score));
+    }
     match board.who_turn() {
-        Side::White => return compute_best_move(board, depth, -127, 127, true).mv,
-        Side::Black => return compute_best_move(board, depth, -127, 127, false).mv,
+        Side::White => scored.sort_by_key(|&(_, score)| -score),
+        Side::Black => scored.sort_by_key(|&(_, score)| score),
     }
+    Some(scored)
+}
+
+/// like `scored_moves`, `None` if `stop` is set during the search
+#[allow(dead_code)]
+pub fn score_moves(board: &Board, depth: usize, stop: &AtomicBool) -> Option<Vec<(Move, i8)>> {
+    let mut search = Search {stop, deadline: None, nodes: 0, aborted: false};
+    scored_moves(board, depth, &mut search)
 }
 
+/// best moves of every depth, `None` if the search was stopped
+#[allow(dead_code)]
+fn principal_variation(board: &Board, first: Move, depth: usize, search: &mut Search) -> Option<Vec<Move>> {
+    let mut board = *board;
+    let mut mvs = vec![first];
+    board.do_move_without_checks(first);
+    for depth in (1..depth).rev() {
+        let mv = search_depth(&board, depth, search).mv;
+        if search.aborted {
+            return None;
+        }
+        match mv {
+            Some(mv) => {
+                board.do_move_without_checks(mv);
+                mvs.push(mv);
+            },
+            None => break,
+        }
+    }
+    Some(mvs)
+}
+
+/// like `search`, but every move of the position gets its exact score,
+/// so `lines` best moves are known with their principal variations
+#[allow(dead_code)]
+pub fn analyze(board: &Board, max_depth: usize, lines: usize, stop: &AtomicBool, mut on_depth: impl FnMut(&Analysis)) -> Option<Analysis> {
+    let mut search = Search {stop, deadline: None, nodes: 0, aborted: false};
+    let mut result = None;
+    for depth in 1..=max_depth {
+        let scored = match scored_moves(board, depth, &mut search) {
+            Some(scored) => scored,
+            None => return result,
+        };
+        if scored.is_empty() {
+            break;
+        }
+
+        let mut analysis = Analysis {depth, nodes: 0, lines: vec![]};
+        for &(mv, score) in scored.iter().take(lines) {
+            let mvs = match principal_variation(board, mv, depth, &mut search) {
+                Some(mvs) => mvs,
+                None => return result,
+            };
+            analysis.lines.push(Line {score, mvs});
+        }
+        analysis.nodes = search.nodes;
+        on_depth(&analysis);
+        let decided = scored[0].1.abs() == 100;
+        result = Some(analysis);
+        if decided {
+            break;
+        }
+    }
+    result
+}
+
+/// `first` and all jumps which must follow it, continuation is chosen by the search with `depth`
+#[allow(dead_code)]
+pub fn complete_turn(board: &Board, first: Move, depth: usize) -> Vec<Move> {
+    let mut board = *board;
+    let side = board.who_turn();
+    let mut mvs = vec![first];
+    board.do_move_without_checks(first);
+    while board.who_turn() == side && !board.is_ended() {
+        let next = match best_move(&mut board.clone(), depth.max(1)) {
+            Some(mv) => mv,
+            None => match board.all_available_moves().first() {
+                Some(&mv) => mv,
+                None => break,
+            },
+        };
+        board.do_move_without_checks(next);
+        mvs.push(next);
+    }
+    mvs
+}
+
+#[allow(dead_code)]
 pub fn chouse_move5(board: &mut Board) -> Option<Move> {
     best_move(board, 5)
 }
 
+#[allow(dead_code)]
 pub fn chouse_move10(board: &mut Board) -> Option<Move> {
     best_move(board, 10)
 }
 
+#[allow(dead_code)]
 pub fn chouse_move15(board: &mut Board) -> Option<Move> {
     best_move(board, 15)
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn analyze_lines() {
+        let stop = AtomicBool::new(false);
+        let board = Board::new();
+        let mut depths = vec![];
+        let analysis = analyze(&board, 4, 3, &stop, |analysis| depths.push(analysis.depth)).unwrap();
+        assert_eq!(depths, vec![1, 2, 3, 4]);
+        assert_eq!(analysis.lines.len(), 3);
+        assert!(analysis.lines.windows(2).all(|lines| lines[0].score >= lines[1].score));
+        assert_eq!(analysis.lines[0].mvs.len(), 4);
+        assert_eq!(analysis.lines[0].score, search_depth(&board, 4, &mut Search {stop: &stop, deadline: None, nodes: 0, aborted: false}).score);
+
+        // black takes the white man and wins
+        let board = Board::from_arr(Side::Black, [
+            [' ', 'b', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', 'b', ' ', ' ', ' ', ' '],
+            [' ', ' ', 'w', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        let analysis = analyze(&board, 10, 5, &stop, |_| ()).unwrap();
+        assert_eq!(analysis.depth, 1);
+        assert_eq!(analysis.lines.len(), 1);
+        assert_eq!(analysis.lines[0].score, -100);
+    }
+
+    #[test]
+    fn score_is_white_relative() {
+        let lone = |side, piece| Board::from_arr(side, [
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', piece, ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+            [' ', ' ', ' ', ' ', ' ', ' ', ' ', ' '],
+        ]).unwrap();
+        // the side to move doesn't change the score of a finished game
+        assert_eq!(count_score(&lone(Side::White, 'w')), 100);
+        assert_eq!(count_score(&lone(Side::Black, 'w')), 100);
+        assert_eq!(count_score(&lone(Side::White, 'b')), -100);
+        assert_eq!(count_score(&lone(Side::Black, 'b')), -100);
+        assert_eq!(count_score(&Board::new()), 0);
+    }
+
+    #[test]
+    fn stopped_analysis() {
+        // the flag is checked once in a while, so the first depths can still finish
+        let stop = AtomicBool::new(true);
+        let analysis = analyze(&Board::new(), 30, 3, &stop, |_| ());
+        assert!(analysis.map(|analysis| analysis.depth).unwrap_or(0) < 10);
+    }
+}
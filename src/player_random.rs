@@ -3,9 +3,9 @@ use crate::board::{Board, Move};
 
 pub fn chouse_move(board: &mut Board) -> Option<Move> {
     let mvs = board.all_available_moves();
-    if mvs.len() == 0 {
+    if mvs.is_empty() {
         return None;
     }
     let mv_i = rand::random::<usize>() % mvs.len();
-    return Some(mvs[mv_i]);
+    Some(mvs[mv_i])
 }
@@ -0,0 +1,252 @@
+//! review of a played game, the engine checks every turn and finds the ones which lost the most
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::board::{Board, Move, Side};
+use crate::game::{Game, Turn};
+use crate::notation;
+use crate::player_minmax;
+
+/// depth of the search for every position, when it isn't given
+pub const DEFAULT_DEPTH: usize = 6;
+/// scores are cut to this, so a missed or a given away win counts like a lot of men
+const MAX_SCORE: i32 = 12;
+/// accuracy a turn loses for every lost man
+const ACCURACY_PER_MAN: f32 = 25.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Judgement {
+    Good,
+    /// one man is lost
+    Inaccuracy,
+    /// two men are lost
+    Mistake,
+    /// more than two men or the game is lost
+    Blunder,
+}
+
+impl Judgement {
+    fn from_loss(loss: i32) -> Judgement {
+        match loss {
+            ..=0 => Judgement::Good,
+            1 => Judgement::Inaccuracy,
+            2 => Judgement::Mistake,
+            _ => Judgement::Blunder,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Judgement::Good => "good",
+            Judgement::Inaccuracy => "inaccuracy",
+            Judgement::Mistake => "mistake",
+            Judgement::Blunder => "blunder",
+        }
+    }
+
+    /// like in annotated games, empty for good turns
+    pub fn mark(&self) -> &'static str {
+        match self {
+            Judgement::Good => "",
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+/// played turn and what the engine thinks of it, scores are positive for white
+pub struct TurnReview {
+    pub side: Side,
+    /// position before the turn
+    pub board: Board,
+    pub mvs: Vec<Move>,
+    pub first_ply: usize,
+    /// the best turn, same as `mvs` for good turns
+    pub best: Vec<Move>,
+    pub best_score: i8,
+    /// score after the played turn
+    pub score: i8,
+    /// men lost by the side, every jump of a multi-jump counts
+    pub loss: i32,
+    pub judgement: Judgement,
+}
+
+pub struct Report {
+    pub depth: usize,
+    pub turns: Vec<TurnReview>,
+}
+
+impl Report {
+    /// from 0 to 100, None if the side didn't play
+    pub fn accuracy(&self, side: Side) -> Option<f32> {
+        let turns: Vec<&TurnReview> = self.turns.iter().filter(|turn| turn.side == side).collect();
+        if turns.is_empty() {
+            return None;
+        }
+        let total: f32 = turns.iter().map(|turn| (100. - turn.loss as f32 * ACCURACY_PER_MAN).max(0.)).sum();
+        Some(total / turns.len() as f32)
+    }
+
+    pub fn count(&self, side: Side, judgement: Judgement) -> usize {
+        self.turns.iter().filter(|turn| turn.side == side && turn.judgement == judgement).count()
+    }
+
+    /// text report with the bad turns and the summary of both sides
+    pub fn format(&self) -> String {
+        let mut result = format!("review at depth {}\n", self.depth);
+        for (i, turn) in self.turns.iter().enumerate() {
+            if turn.judgement == Judgement::Good {
+                continue;
+            }
+            result.push_str(&format!(
+                "{} {}{} {}, better {} ({} instead of {})\n",
+                turn_number(&self.turns, i),
+                notation::format_turn(&turn.board, &turn.mvs),
+                turn.judgement.mark(),
+                turn.judgement.name(),
+                notation::format_turn(&turn.board, &turn.best),
+                notation::format_score(turn.best_score),
+                notation::format_score(turn.score),
+            ));
+        }
+        for side in [Side::White, Side::Black] {
+            let accuracy = match self.accuracy(side) {
+                Some(accuracy) => format!("{:.0}%", accuracy),
+                None => "-".to_string(),
+            };
+            result.push_str(&format!(
+                "{}: accuracy {}, inaccuracies {}, mistakes {}, blunders {}\n",
+                side,
+                accuracy,
+                self.count(side, Judgement::Inaccuracy),
+                self.count(side, Judgement::Mistake),
+                self.count(side, Judgement::Blunder),
+            ));
+        }
+        result
+    }
+}
+
+/// like "12." for white and "12..." for black
+pub fn turn_number(turns: &[TurnReview], i: usize) -> String {
+    let offset = match turns.first().map(|turn| turn.side) {
+        Some(Side::Black) => 1,
+        _ => 0,
+    };
+    let number = (i + offset) / 2 + 1;
+    match turns[i].side {
+        Side::White => format!("{}.", number),
+        Side::Black => format!("{}...", number),
+    }
+}
+
+fn clamped(score: i8) -> i32 {
+    (score as i32).clamp(-MAX_SCORE, MAX_SCORE)
+}
+
+/// every jump of the turn is compared with the best one, None if `stop` is set
+pub fn review_turn(turn: &Turn, depth: usize, stop: &AtomicBool) -> Option<TurnReview> {
+    let sign = match turn.side {
+        Side::White => 1,
+        Side::Black => -1,
+    };
+    let mut board = turn.board;
+    let mut loss = 0;
+    let mut best_first = None;
+    let mut best_score = 0;
+    let mut score = 0;
+    for &mv in &turn.mvs {
+        if stop.load(Ordering::Relaxed) {
+            return None;
+        }
+        let scored = player_minmax::score_moves(&board, depth, stop)?;
+        let &(best_mv, best) = scored.first()?;
+        let played = scored.iter().find(|&&(scored_mv, _)| scored_mv == mv).map_or(best, |&(_, played)| played);
+        if best_first.is_none() {
+            // the played move is kept, if it is as good as the best one
+            best_first = Some(if played == best { mv } else { best_mv });
+            best_score = best;
+        }
+        loss += sign * (clamped(best) - clamped(played));
+        score = played;
+        board.do_move_without_checks(mv);
+    }
+    let judgement = Judgement::from_loss(loss);
+    let best = match (judgement, best_first) {
+        (Judgement::Good, _) | (_, None) => turn.mvs.clone(),
+        (_, Some(first)) => player_minmax::complete_turn(&turn.board, first, depth),
+    };
+    Some(TurnReview {
+        side: turn.side,
+        board: turn.board,
+        mvs: turn.mvs.clone(),
+        first_ply: turn.first_ply,
+        best,
+        best_score,
+        score,
+        loss: loss.max(0),
+        judgement,
+    })
+}
+
+/// all turns of the current line, `on_turn` gets the amount of reviewed and all turns
+pub fn review(game: &Game, depth: usize, stop: &AtomicBool, mut on_turn: impl FnMut(usize, usize)) -> Option<Report> {
+    let turns = game.turns();
+    let mut reviews = vec![];
+    for turn in &turns {
+        reviews.push(review_turn(turn, depth, stop)?);
+        on_turn(reviews.len(), turns.len());
+    }
+    Some(Report {depth, turns: reviews})
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Square;
+
+    fn sq(number: usize) -> Square {
+        Square::from_number(number).unwrap()
+    }
+
+    fn game_from(fen: &str, moves: &[&str]) -> Game {
+        let mut game = Game::from_board(notation::parse_fen(fen).unwrap());
+        for text in moves {
+            for mv in notation::parse_move(text).unwrap() {
+                game.do_move(mv).unwrap();
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn finds_blunder_and_better_turn() {
+        // black man on 14 takes the man on 18, while on 17 it is covered by 21
+        let game = game_from("W:W21,22:B14,1", &["22-18"]);
+        let report = review(&game, 4, &AtomicBool::new(false), |_, _| ()).unwrap();
+        let turn = &report.turns[0];
+        assert!(turn.judgement >= Judgement::Inaccuracy);
+        assert_eq!(turn.best, vec![Move::new(sq(22), sq(17))]);
+        assert!(turn.best_score > turn.score);
+        assert_eq!(report.count(Side::White, turn.judgement), 1);
+        assert!(report.accuracy(Side::White).unwrap() < 100.);
+        assert_eq!(report.accuracy(Side::Black), None);
+        assert!(report.format().contains("1. 22-18?"));
+    }
+
+    #[test]
+    fn good_game_and_progress() {
+        let game = game_from("W:W21,22:B14,1", &["22-17", "14-18"]);
+        let mut progress = vec![];
+        let report = review(&game, 4, &AtomicBool::new(false), |done, total| progress.push((done, total))).unwrap();
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+        assert_eq!(report.turns[0].judgement, Judgement::Good);
+        assert_eq!(report.turns[0].best, report.turns[0].mvs);
+        assert_eq!(report.accuracy(Side::White), Some(100.));
+        assert_eq!(turn_number(&report.turns, 1), "1...");
+
+        assert!(review(&game, 4, &AtomicBool::new(true), |_, _| ()).is_none());
+    }
+}
@@ -0,0 +1,205 @@
+//! http api with json bodies, positions are pdn fen like "W:W21-32:B1-12"
+//!
+//! `POST /moves` {"position"} -> moves available in the position
+//! `POST /apply` {"position", "move"} -> position after the move
+//! `POST /bestmove` {"position", "depth"?, "time"?} -> whole turn chosen by the min max player
+
+use std::error::Error;
+use std::io::{self, Write};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::board::{Board, State};
+use crate::notation;
+use crate::player_minmax;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8000";
+const DEFAULT_DEPTH: usize = 5;
+/// deeper searches take minutes, a request must not hang the server
+const MAX_DEPTH: usize = 20;
+/// longest thinking time of a request, for the same reason
+const MAX_TIME: Duration = Duration::from_secs(60);
+
+const SERVE_USAGE: &str = "usage: checkers serve [address]
+default address is 127.0.0.1:8000";
+
+#[derive(Deserialize)]
+struct MovesRequest {
+    position: String,
+}
+
+#[derive(Deserialize)]
+struct ApplyRequest {
+    position: String,
+    #[serde(rename = "move")]
+    mv: String,
+}
+
+#[derive(Deserialize)]
+struct BestMoveRequest {
+    position: String,
+    depth: Option<usize>,
+    /// seconds
+    time: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct MoveInfo {
+    from: usize,
+    to: usize,
+    notation: String,
+    capture: bool,
+}
+
+#[derive(Serialize)]
+struct MovesResponse {
+    state: &'static str,
+    moves: Vec<MoveInfo>,
+}
+
+#[derive(Serialize)]
+struct ApplyResponse {
+    position: String,
+    state: &'static str,
+}
+
+#[derive(Serialize)]
+struct BestMoveResponse {
+    /// None when there are no moves
+    #[serde(rename = "move")]
+    mv: Option<String>,
+    /// positive numbers is good for white
+    score: i8,
+    depth: usize,
+    nodes: u64,
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// error with http status
+struct ApiError(u16, String);
+
+impl<E: Error> From<E> for ApiError {
+    fn from(e: E) -> Self {
+        ApiError(400, e.to_string())
+    }
+}
+
+fn state_name(board: &Board) -> &'static str {
+    match board.state() {
+        State::WhiteTurn => "white_turn",
+        State::BlackTurn => "black_turn",
+        State::WhiteWin => "white_win",
+        State::BlackWin => "black_win",
+        State::Draw(_) => "draw",
+    }
+}
+
+fn moves(request: MovesRequest) -> Result<MovesResponse, ApiError> {
+    let board = notation::parse_fen(&request.position)?;
+    let moves = board.clone().all_available_moves().into_iter().map(|mv| MoveInfo {
+        from: mv.from.number(),
+        to: mv.to.number(),
+        notation: notation::format_turn(&board, &[mv]),
+        capture: notation::is_capture(&board, mv),
+    }).collect();
+    Ok(MovesResponse {state: state_name(&board), moves})
+}
+
+fn apply(request: ApplyRequest) -> Result<ApplyResponse, ApiError> {
+    let mut board = notation::parse_fen(&request.position)?;
+    let side = board.who_turn();
+    for mv in notation::parse_move(&request.mv)? {
+        board.do_move(mv)?;
+    }
+    // fen can't keep the piece which must go on jumping
+    if board.who_turn() == side && !board.is_ended() {
+        return Err(ApiError(400, format!("{} (turn is not finished)", request.mv)));
+    }
+    Ok(ApplyResponse {position: notation::format_fen(&board), state: state_name(&board)})
+}
+
+fn best_move(request: BestMoveRequest) -> Result<BestMoveResponse, ApiError> {
+    let board = notation::parse_fen(&request.position)?;
+    let (depth, deadline) = match (request.depth, request.time) {
+        (depth, Some(time)) if time > 0.0 => {
+            let too_long = || ApiError(400, format!("time must be at most {} seconds", MAX_TIME.as_secs()));
+            let time = Duration::try_from_secs_f64(time).ok().filter(|&time| time <= MAX_TIME).ok_or_else(too_long)?;
+            (depth.unwrap_or(MAX_DEPTH), Some(Instant::now().checked_add(time).ok_or_else(too_long)?))
+        },
+        (Some(depth), _) => (depth, None),
+        (None, _) => (DEFAULT_DEPTH, None),
+    };
+    if depth == 0 || depth > MAX_DEPTH {
+        return Err(ApiError(400, format!("depth must be from 1 to {}", MAX_DEPTH)));
+    }
+
+    let stop = AtomicBool::new(false);
+    let info = player_minmax::search(&board, depth, deadline, &stop, |_| ());
+    let first = match info.and_then(|info| info.mv) {
+        Some(mv) => Some(mv),
+        // time was too short even for the first depth
+        None => board.clone().all_available_moves().first().copied(),
+    };
+    let mv = first.map(|mv| {
+        // next jumps aren't limited by the time, so their depth is
+        let turn = player_minmax::complete_turn(&board, mv, info.map(|info| info.depth).unwrap_or(1).min(DEFAULT_DEPTH));
+        notation::format_turn(&board, &turn)
+    });
+    Ok(BestMoveResponse {
+        mv,
+        score: info.map(|info| info.score).unwrap_or(0),
+        depth: info.map(|info| info.depth).unwrap_or(0),
+        nodes: info.map(|info| info.nodes).unwrap_or(0),
+    })
+}
+
+/// read json body, run `handler` and write its answer as json
+fn json<T: for<'a> Deserialize<'a>, R: Serialize>(request: &mut Request, handler: fn(T) -> Result<R, ApiError>) -> Result<String, ApiError> {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+    let response = handler(serde_json::from_str(&body)?)?;
+    Ok(serde_json::to_string(&response)?)
+}
+
+fn handle(mut request: Request) {
+    let result = match (request.method(), request.url()) {
+        (Method::Post, "/moves") => json(&mut request, moves),
+        (Method::Post, "/apply") => json(&mut request, apply),
+        (Method::Post, "/bestmove") => json(&mut request, best_move),
+        (_, "/moves") | (_, "/apply") | (_, "/bestmove") => Err(ApiError(405, "use POST".to_string())),
+        (_, url) => Err(ApiError(404, format!("unknown path \"{}\"", url))),
+    };
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err(ApiError(status, error)) => (status, serde_json::to_string(&ErrorResponse {error}).unwrap()),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let _ = request.respond(Response::from_string(body).with_status_code(status).with_header(header));
+}
+
+pub fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", SERVE_USAGE);
+        return Ok(());
+    }
+    let address = args.first().map(|arg| arg.as_str()).unwrap_or(DEFAULT_ADDRESS);
+    let server = Server::http(address).map_err(|e| e.to_string())?;
+    match server.server_addr().to_ip() {
+        Some(address) => println!("listening on {}", address),
+        None => println!("listening on {}", address),
+    }
+    io::stdout().flush()?;
+
+    // searches are long, so every request gets its own thread
+    for request in server.incoming_requests() {
+        std::thread::spawn(move || handle(request));
+    }
+    Ok(())
+}
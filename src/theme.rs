@@ -0,0 +1,339 @@
+//! looks of the board in the window, every choice is saved with the settings
+
+use std::path::PathBuf;
+
+use egui::Color32;
+
+use crate::assets;
+use crate::board::{Cell, Square};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum BoardStyle {
+    Classic,
+    Wood,
+    Tournament,
+    Ocean,
+}
+
+impl BoardStyle {
+    pub const ALL: [BoardStyle; 4] = [BoardStyle::Classic, BoardStyle::Wood, BoardStyle::Tournament, BoardStyle::Ocean];
+
+    /// name for the storage and the menu
+    pub fn name(self) -> &'static str {
+        match self {
+            BoardStyle::Classic => "classic",
+            BoardStyle::Wood => "wood",
+            BoardStyle::Tournament => "tournament",
+            BoardStyle::Ocean => "ocean",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        BoardStyle::ALL.into_iter().find(|style| style.name() == name)
+    }
+
+    /// squares the pieces stand on
+    pub fn dark_square(self) -> Color32 {
+        match self {
+            BoardStyle::Classic => Color32::BLACK,
+            BoardStyle::Wood => Color32::from_rgb(118, 78, 46),
+            BoardStyle::Tournament => Color32::from_rgb(58, 110, 62),
+            BoardStyle::Ocean => Color32::from_rgb(52, 84, 128),
+        }
+    }
+
+    pub fn light_square(self) -> Color32 {
+        match self {
+            BoardStyle::Classic => Color32::DARK_GRAY,
+            BoardStyle::Wood => Color32::from_rgb(226, 192, 142),
+            BoardStyle::Tournament => Color32::from_rgb(232, 230, 205),
+            BoardStyle::Ocean => Color32::from_rgb(196, 212, 228),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum PieceSet {
+    /// png images
+    Classic,
+    /// svg images
+    Flat,
+}
+
+impl PieceSet {
+    pub const ALL: [PieceSet; 2] = [PieceSet::Classic, PieceSet::Flat];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceSet::Classic => "classic",
+            PieceSet::Flat => "flat",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        PieceSet::ALL.into_iter().find(|pieces| pieces.name() == name)
+    }
+
+    /// built in image of the piece, None for empty squares
+    pub fn uri(self, cell: Cell) -> Option<String> {
+        let name = file_name(cell)?;
+        let path = match self {
+            PieceSet::Classic => format!("classic/{}.png", name),
+            PieceSet::Flat => format!("flat/{}.svg", name),
+        };
+        Some(assets::uri(&path))
+    }
+}
+
+/// name of the image without the extension, like in `assets`
+fn file_name(cell: Cell) -> Option<&'static str> {
+    match cell {
+        Cell::Empty => None,
+        Cell::Black => Some("black_checker"),
+        Cell::BlackKing => Some("black_king"),
+        Cell::White => Some("white_checker"),
+        Cell::WhiteKing => Some("white_king"),
+    }
+}
+
+/// images of the user like "white_king.svg" or "white_king.png", they replace ones of the piece set
+#[derive(Debug, PartialEq, Clone)]
+pub struct PieceDir {
+    pub path: PathBuf,
+    /// images which are found in the directory
+    uris: Vec<(Cell, String)>,
+}
+
+impl PieceDir {
+    /// the directory is read once, missing images are taken from the piece set
+    pub fn open(path: PathBuf) -> PieceDir {
+        let mut uris = vec![];
+        for cell in [Cell::White, Cell::WhiteKing, Cell::Black, Cell::BlackKing] {
+            let name = file_name(cell).unwrap_or_default();
+            let found = ["svg", "png"].iter().map(|extension| path.join(format!("{}.{}", name, extension))).find(|file| file.is_file());
+            if let Some(file) = found {
+                uris.push((cell, format!("file://{}", file.display())));
+            }
+        }
+        PieceDir {path, uris}
+    }
+
+    pub fn uri(&self, cell: Cell) -> Option<&str> {
+        self.uris.iter().find(|(found, _)| *found == cell).map(|(_, uri)| uri.as_str())
+    }
+
+    /// amount of pieces with images in the directory
+    pub fn found(&self) -> usize {
+        self.uris.len()
+    }
+}
+
+/// labels around the board or on its squares
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Coordinates {
+    Hidden,
+    /// columns and rows from 0 to 7
+    Indices,
+    /// files from a to h and ranks from 1 to 8
+    Algebraic,
+    /// numbers of the squares from 1 to 32 on the squares
+    Numbers,
+}
+
+impl Coordinates {
+    pub const ALL: [Coordinates; 4] = [Coordinates::Hidden, Coordinates::Indices, Coordinates::Algebraic, Coordinates::Numbers];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Coordinates::Hidden => "hidden",
+            Coordinates::Indices => "indices",
+            Coordinates::Algebraic => "algebraic",
+            Coordinates::Numbers => "numbers",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Coordinates::ALL.into_iter().find(|coordinates| coordinates.name() == name)
+    }
+
+    /// label over column `x` of the board
+    pub fn column(self, x: usize) -> String {
+        match self {
+            Coordinates::Indices => x.to_string(),
+            Coordinates::Algebraic => ((b'a' + x as u8) as char).to_string(),
+            Coordinates::Hidden | Coordinates::Numbers => String::new(),
+        }
+    }
+
+    /// label left of row `y` of the board
+    pub fn row(self, y: usize) -> String {
+        match self {
+            Coordinates::Indices => y.to_string(),
+            Coordinates::Algebraic => (8 - y).to_string(),
+            Coordinates::Hidden | Coordinates::Numbers => String::new(),
+        }
+    }
+
+    /// small label in the corner of the square
+    pub fn square(self, square: Square) -> Option<String> {
+        match self {
+            Coordinates::Numbers => Some(square.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// colors of the marks on the board
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Palette {
+    Standard,
+    /// Okabe-Ito colors, which differ for every kind of color blindness
+    Colorblind,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 2] = [Palette::Standard, Palette::Colorblind];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Standard => "standard",
+            Palette::Colorblind => "colorblind",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Palette::ALL.into_iter().find(|palette| palette.name() == name)
+    }
+
+    /// pieces which can move and squares they can move to
+    pub fn available(self) -> Color32 {
+        match self {
+            Palette::Standard => Color32::DARK_GREEN,
+            Palette::Colorblind => Color32::from_rgb(0, 114, 178),
+        }
+    }
+
+    /// square of the last move
+    pub fn last_move(self) -> Color32 {
+        match self {
+            Palette::Standard => Color32::DARK_RED,
+            Palette::Colorblind => Color32::from_rgb(213, 94, 0),
+        }
+    }
+
+    pub fn hint(self) -> Color32 {
+        match self {
+            Palette::Standard => Color32::LIGHT_BLUE,
+            Palette::Colorblind => Color32::from_rgb(86, 180, 233),
+        }
+    }
+
+    /// pieces of the player which can be taken
+    pub fn threat(self) -> Color32 {
+        match self {
+            Palette::Standard => Color32::RED,
+            Palette::Colorblind => Color32::from_rgb(230, 159, 0),
+        }
+    }
+
+    /// pieces of the opponent which can be taken
+    pub fn en_prise(self) -> Color32 {
+        match self {
+            Palette::Standard => Color32::GOLD,
+            Palette::Colorblind => Color32::from_rgb(240, 228, 66),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Theme {
+    pub board: BoardStyle,
+    pub pieces: PieceSet,
+    pub piece_dir: Option<PieceDir>,
+    pub coordinates: Coordinates,
+    pub palette: Palette,
+    /// light widgets instead of dark ones
+    pub light: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            board: BoardStyle::Classic,
+            pieces: PieceSet::Classic,
+            piece_dir: None,
+            coordinates: Coordinates::Indices,
+            palette: Palette::Standard,
+            light: false,
+        }
+    }
+}
+
+impl Theme {
+    /// image of the piece from the directory of the user or the piece set
+    pub fn piece_uri(&self, cell: Cell) -> Option<String> {
+        let custom = self.piece_dir.as_ref().and_then(|dir| dir.uri(cell));
+        custom.map(|uri| uri.to_string()).or_else(|| self.pieces.uri(cell))
+    }
+
+    pub fn visuals(&self) -> egui::Visuals {
+        match self.light {
+            true => egui::Visuals::light(),
+            false => egui::Visuals::dark(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for style in BoardStyle::ALL {
+            assert_eq!(BoardStyle::from_name(style.name()), Some(style));
+        }
+        for pieces in PieceSet::ALL {
+            assert_eq!(PieceSet::from_name(pieces.name()), Some(pieces));
+        }
+        for coordinates in Coordinates::ALL {
+            assert_eq!(Coordinates::from_name(coordinates.name()), Some(coordinates));
+        }
+        for palette in Palette::ALL {
+            assert_eq!(Palette::from_name(palette.name()), Some(palette));
+        }
+        assert_eq!(BoardStyle::from_name("marble"), None);
+    }
+
+    #[test]
+    fn labels_and_images() {
+        assert_eq!(Coordinates::Algebraic.column(2), "c");
+        assert_eq!(Coordinates::Algebraic.row(5), "3");
+        assert_eq!(Coordinates::Indices.row(5), "5");
+        assert_eq!(Coordinates::Numbers.column(2), "");
+        assert_eq!(Coordinates::Numbers.square(Square::from_number(22).unwrap()), Some("22".to_string()));
+        assert_eq!(Coordinates::Algebraic.square(Square::from_number(22).unwrap()), None);
+
+        assert_eq!(PieceSet::Flat.uri(Cell::BlackKing).as_deref(), Some("bytes://pieces/flat/black_king.svg"));
+        assert_eq!(PieceSet::Classic.uri(Cell::Empty), None);
+        for palette in Palette::ALL {
+            assert_ne!(palette.threat(), palette.en_prise());
+        }
+    }
+
+    #[test]
+    fn custom_pieces_replace_the_set() {
+        let path = std::env::temp_dir().join(format!("checkers-pieces-{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("white_king.svg"), "<svg/>").unwrap();
+        std::fs::write(path.join("black_checker.png"), "").unwrap();
+        let dir = PieceDir::open(path.clone());
+        std::fs::remove_dir_all(&path).unwrap();
+        assert_eq!(dir.found(), 2);
+
+        let theme = Theme {piece_dir: Some(dir), ..Theme::default()};
+        assert_eq!(theme.piece_uri(Cell::WhiteKing), Some(format!("file://{}", path.join("white_king.svg").display())));
+        assert_eq!(theme.piece_uri(Cell::White), PieceSet::Classic.uri(Cell::White));
+    }
+}
@@ -0,0 +1,378 @@
+use std::error::Error;
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::board::{Cell, Move, Side, Square};
+use crate::cli;
+use crate::game::Game;
+use crate::notation;
+
+/// names for `cli::parse_opponent` and their labels in the menu
+const GAME_MODES: [(&str, &str); 5] = [
+    ("none", "self play"),
+    ("random", "random"),
+    ("minmax5", "min max 5"),
+    ("minmax10", "min max 10"),
+    ("minmax15", "min max 15"),
+];
+
+const KEYS_HELP: &str = "arrows: move  enter: select  m: game mode  s: side  r: restart  d: draw  q: quit";
+
+enum Popup {
+    GameMode(ListState),
+    ChangeSide(ListState),
+    GameEnded,
+}
+
+struct App {
+    game: Game,
+    game_mode: usize,
+    opponent: Option<cli::ChouseMove>,
+    player_side: Side,
+    cursor_x: usize,
+    cursor_y: usize,
+    highlighted: Vec<Square>,
+    selected_cell: Option<Square>,
+    moved_from: Vec<Square>,
+    moved_to: Option<Square>,
+    message: Option<String>,
+    popup: Option<Popup>,
+    quit: bool,
+}
+
+impl App {
+    fn new(game_mode: usize, player_side: Side) -> Self {
+        let mut app = App {
+            game: Game::new(),
+            game_mode,
+            opponent: cli::parse_opponent(GAME_MODES[game_mode].0).unwrap(),
+            player_side,
+            cursor_x: 0,
+            cursor_y: 0,
+            highlighted: vec![],
+            selected_cell: None,
+            moved_from: vec![],
+            moved_to: None,
+            message: None,
+            popup: None,
+            quit: false,
+        };
+        app.restart();
+        app
+    }
+
+    fn restart(&mut self) {
+        self.game = Game::new();
+        self.popup = None;
+        self.selected_cell = None;
+        self.moved_from = vec![];
+        self.moved_to = None;
+        self.message = None;
+        (self.cursor_x, self.cursor_y) = match self.player_side {
+            Side::White => (0, 7),
+            Side::Black => (7, 0),
+        };
+        self.enemy_try_move();
+        self.highlight_available_checkers_to_move();
+    }
+
+    fn change_game_mode(&mut self, game_mode: usize) {
+        self.game_mode = game_mode;
+        self.opponent = cli::parse_opponent(GAME_MODES[game_mode].0).unwrap();
+        self.restart();
+    }
+
+    fn highlight_available_checkers_to_move(&mut self) {
+        self.highlighted = self.game.board().clone().all_available_moves().iter().map(|mv| mv.from).collect();
+    }
+
+    fn enemy_try_move(&mut self) {
+        let chouse_func = match self.opponent {
+            Some(f) => f,
+            None => return,
+        };
+        if self.game.board().who_turn() == self.player_side || self.game.board().is_ended() {
+            return;
+        }
+        match cli::play_turn(&mut self.game, chouse_func) {
+            Ok(mvs) => {
+                self.moved_from = mvs.iter().map(|mv| mv.from).collect();
+                self.moved_to = mvs.last().map(|mv| mv.to);
+            },
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    /// return true if move success else - return false
+    fn try_move(&mut self, square: Square) -> bool {
+        match self.selected_cell {
+            None => false,
+            Some(selected_cell) => match self.game.do_move(Move::new(selected_cell, square)) {
+                Ok(()) => {
+                    self.message = None;
+                    self.selected_cell = None;
+                    self.enemy_try_move();
+                    self.highlight_available_checkers_to_move();
+                    true
+                },
+                Err(e) => {
+                    self.message = Some(format!("move unavailable: {}", e));
+                    false
+                },
+            },
+        }
+    }
+
+    fn on_select(&mut self) {
+        let square = match Square::new(self.cursor_x, self.cursor_y) {
+            Ok(square) => square,
+            Err(_) => {
+                self.selected_cell = None;
+                self.highlight_available_checkers_to_move();
+                return;
+            },
+        };
+
+        match self.game.board().get_cell(square) {
+            Cell::Empty => {
+                if !self.try_move(square) {
+                    self.selected_cell = None;
+                    self.highlight_available_checkers_to_move();
+                }
+            },
+            _ => {
+                let available_moves = self.game.board().available_moves_for_cell(square);
+                if available_moves.is_empty() {
+                    self.selected_cell = None;
+                    self.highlight_available_checkers_to_move();
+                } else {
+                    self.selected_cell = Some(square);
+                    self.highlighted = available_moves.iter().map(|mv| mv.to).collect();
+                }
+            },
+        }
+
+        if self.game.board().is_ended() {
+            self.popup = Some(Popup::GameEnded);
+        }
+    }
+
+    /// dx and dy are in screen directions, board is flipped when playing black
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (dx, dy) = match self.player_side {
+            Side::White => (dx, dy),
+            Side::Black => (-dx, -dy),
+        };
+        self.cursor_x = (self.cursor_x as i32 + dx).clamp(0, 7) as usize;
+        self.cursor_y = (self.cursor_y as i32 + dy).clamp(0, 7) as usize;
+    }
+
+    /// `state` is taken from the popup, it is put back unless the menu is closed
+    fn handle_menu_key(&mut self, code: KeyCode, is_game_mode: bool, mut state: ListState) {
+        let len = if is_game_mode { GAME_MODES.len() } else { 2 };
+        let selected = state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Up => state.select(Some(selected.saturating_sub(1))),
+            KeyCode::Down => state.select(Some((selected + 1).min(len - 1))),
+            KeyCode::Enter => {
+                if is_game_mode {
+                    self.change_game_mode(selected);
+                } else {
+                    self.player_side = if selected == 0 { Side::White } else { Side::Black };
+                    self.restart();
+                }
+                return;
+            },
+            KeyCode::Esc => return,
+            KeyCode::Char('q') => {
+                self.quit = true;
+                return;
+            },
+            _ => (),
+        }
+        self.popup = Some(match is_game_mode {
+            true => Popup::GameMode(state),
+            false => Popup::ChangeSide(state),
+        });
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        match self.popup.take() {
+            Some(Popup::GameMode(state)) => self.handle_menu_key(code, true, state),
+            Some(Popup::ChangeSide(state)) => self.handle_menu_key(code, false, state),
+            Some(Popup::GameEnded) => match code {
+                KeyCode::Char('r') | KeyCode::Enter => self.restart(),
+                KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                _ => self.popup = Some(Popup::GameEnded),
+            },
+            None => match code {
+                KeyCode::Up => self.move_cursor(0, -1),
+                KeyCode::Down => self.move_cursor(0, 1),
+                KeyCode::Left => self.move_cursor(-1, 0),
+                KeyCode::Right => self.move_cursor(1, 0),
+                KeyCode::Enter | KeyCode::Char(' ') => self.on_select(),
+                KeyCode::Char('m') => self.popup = Some(Popup::GameMode(ListState::default().with_selected(Some(self.game_mode)))),
+                KeyCode::Char('s') => {
+                    let selected = if self.player_side == Side::White { 0 } else { 1 };
+                    self.popup = Some(Popup::ChangeSide(ListState::default().with_selected(Some(selected))));
+                },
+                KeyCode::Char('r') => self.restart(),
+                KeyCode::Char('d') => {
+                    self.game.agree_draw();
+                    self.popup = Some(Popup::GameEnded);
+                },
+                KeyCode::Char('q') => self.quit = true,
+                _ => (),
+            },
+        }
+    }
+
+    fn cell_span(&self, x: usize, y: usize) -> Span<'static> {
+        let square = Square::new(x, y).ok();
+        let mut style = match square {
+            None => Style::default().bg(Color::DarkGray),
+            Some(square) if self.selected_cell == Some(square) => Style::default().bg(Color::Blue),
+            Some(square) if self.highlighted.contains(&square) => Style::default().bg(Color::Green),
+            Some(square) if self.moved_to == Some(square) => Style::default().bg(Color::Red),
+            Some(_) => Style::default().bg(Color::Black),
+        };
+        if (x, y) == (self.cursor_x, self.cursor_y) {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+
+        let text = match square.map(|square| self.game.board().get_cell(square)) {
+            None => "   ".to_string(),
+            Some(Cell::Empty) => match self.moved_from.iter().position(|&r| Some(r) == square) {
+                Some(index) => format!(" {} ", index),
+                None => "   ".to_string(),
+            },
+            Some(cell) => {
+                style = match cell {
+                    Cell::White | Cell::WhiteKing => style.fg(Color::White),
+                    _ => style.fg(Color::LightRed),
+                }.add_modifier(Modifier::BOLD);
+                format!(" {} ", cell)
+            },
+        };
+        Span::styled(text, style)
+    }
+
+    fn render_board(&self, frame: &mut Frame, area: Rect) {
+        let order: Vec<usize> = match self.player_side {
+            Side::White => (0..8).collect(),
+            Side::Black => (0..8).rev().collect(),
+        };
+        let files: String = order.iter().map(|&x| format!(" {} ", (b'a' + x as u8) as char)).collect();
+
+        let mut lines = vec![Line::from(format!("   {}", files))];
+        for &y in order.iter() {
+            let mut spans = vec![Span::raw(format!(" {} ", 8 - y))];
+            for &x in order.iter() {
+                spans.push(self.cell_span(x, y));
+            }
+            spans.push(Span::raw(format!(" {}", 8 - y)));
+            lines.push(Line::from(spans));
+        }
+        lines.push(Line::from(format!("   {}", files)));
+
+        let title = format!(" {} vs {} ", self.player_side, GAME_MODES[self.game_mode].1);
+        frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+
+    fn render_history(&self, frame: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = vec![];
+        let mut number = 0;
+        for turn in self.game.turns() {
+            let text = notation::format_turn(&turn.board, &turn.mvs);
+            match turn.side {
+                Side::White => {
+                    number += 1;
+                    lines.push(Line::from(format!("{:>3}. {}", number, text)));
+                },
+                Side::Black => match lines.last_mut() {
+                    Some(line) if turn.first_ply > 0 => line.push_span(Span::raw(format!(" {}", text))),
+                    _ => lines.push(Line::from(format!("{:>3}. ... {}", number.max(1), text))),
+                },
+            }
+        }
+
+        let height = area.height.saturating_sub(2) as usize;
+        let scroll = lines.len().saturating_sub(height) as u16;
+        let block = Block::default().borders(Borders::ALL).title(" moves ");
+        frame.render_widget(Paragraph::new(lines).block(block).scroll((scroll, 0)), area);
+    }
+
+    fn render_popup(&mut self, frame: &mut Frame) {
+        let result = cli::game_result(self.game.board());
+        let (title, items, state) = match &mut self.popup {
+            None => return,
+            Some(Popup::GameMode(state)) => (" game mode ", GAME_MODES.iter().map(|m| m.1.to_string()).collect(), Some(state)),
+            Some(Popup::ChangeSide(state)) => (" change side ", vec!["white".to_string(), "black".to_string()], Some(state)),
+            Some(Popup::GameEnded) => (" end of the game ", vec![result, "r: restart  q: quit".to_string()], None),
+        };
+
+        let width = items.iter().map(|item| item.len()).max().unwrap_or(0).max(title.len()) as u16 + 6;
+        let area = popup_area(frame.area(), width, items.len() as u16 + 2);
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_widget(Clear, area);
+        match state {
+            Some(state) => frame.render_stateful_widget(list, area, state),
+            None => frame.render_widget(list, area),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [board_area, history_area] = Layout::horizontal([Constraint::Length(33), Constraint::Min(20)]).areas(main);
+        let [board_area, _] = Layout::vertical([Constraint::Length(12), Constraint::Min(0)]).areas(board_area);
+
+        self.render_board(frame, board_area);
+        self.render_history(frame, history_area);
+        let status_text = match &self.message {
+            Some(message) => message.clone(),
+            None => KEYS_HELP.to_string(),
+        };
+        frame.render_widget(Paragraph::new(status_text), status);
+        self.render_popup(frame);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key.code);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(area);
+    area
+}
+
+/// full screen game in the terminal, `opponent` is a name for `cli::parse_opponent`
+pub fn run(opponent: &str, player_side: Side) -> Result<(), Box<dyn Error>> {
+    let game_mode = match GAME_MODES.iter().position(|m| m.0 == opponent) {
+        Some(i) => i,
+        None => return Err(format!("unknown opponent \"{}\"", opponent).into()),
+    };
+    let mut app = App::new(game_mode, player_side);
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    Ok(result?)
+}
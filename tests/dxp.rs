@@ -0,0 +1,86 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// start `checkers dxp listen` on a free port, return the process and its address
+fn listen(opponent: &str) -> (Child, BufReader<ChildStdout>, String) {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_checkers"))
+        .args(["dxp", "listen", "127.0.0.1:0", "--opponent", opponent])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut output = BufReader::new(server.stdout.take().unwrap());
+    let mut line = String::new();
+    output.read_line(&mut line).unwrap();
+    let address = line.trim().strip_prefix("listening on ").unwrap().to_string();
+    (server, output, address)
+}
+
+fn result_line(output: &str) -> String {
+    output.lines().find(|line| line.starts_with("result: ")).unwrap().to_string()
+}
+
+fn send(stream: &mut TcpStream, message: &str) {
+    stream.write_all(message.as_bytes()).unwrap();
+    stream.write_all(&[0]).unwrap();
+}
+
+fn receive(stream: &mut TcpStream) -> String {
+    let mut message = vec![];
+    let mut byte = [0];
+    loop {
+        stream.read_exact(&mut byte).unwrap();
+        if byte[0] == 0 {
+            return String::from_utf8(message).unwrap();
+        }
+        message.push(byte[0]);
+    }
+}
+
+#[test]
+fn full_game_between_two_instances() {
+    let (mut server, mut server_output, address) = listen("minmax5");
+    let client = Command::new(env!("CARGO_BIN_EXE_checkers"))
+        .args(["dxp", "connect", &address, "--opponent", "random", "--side", "white"])
+        .output()
+        .unwrap();
+    assert!(client.status.success());
+
+    let mut server_text = String::new();
+    server_output.read_to_string(&mut server_text).unwrap();
+    assert!(server.wait().unwrap().success());
+
+    let client_text = String::from_utf8(client.stdout).unwrap();
+    assert!(client_text.contains("white played "));
+    assert!(server_text.contains("black played "));
+    assert_ne!(result_line(&client_text), "result: game is not ended");
+    assert_eq!(result_line(&client_text), result_line(&server_text));
+}
+
+#[test]
+fn chat_and_take_back() {
+    let (mut server, _server_output, address) = listen("minmax5");
+    let mut stream = TcpStream::connect(&address).unwrap();
+
+    // the server plays black
+    send(&mut stream, &format!("R01{:<32}Z005075A", "test"));
+    let accept = receive(&mut stream);
+    assert!(accept.starts_with("Acheckers"));
+    assert!(accept.ends_with("0"));
+
+    send(&mut stream, "Chello");
+    send(&mut stream, "M0000221800");
+    let answer = receive(&mut stream);
+    assert!(answer.starts_with("M"));
+
+    // back to the first move of white, then play it again
+    send(&mut stream, "B001W");
+    assert_eq!(receive(&mut stream), "K0");
+    send(&mut stream, "M0000211700");
+    assert!(receive(&mut stream).starts_with("M"));
+
+    send(&mut stream, "E11");
+    assert_eq!(receive(&mut stream), "E31");
+    drop(stream);
+    assert!(server.wait().unwrap().success());
+}
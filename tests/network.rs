@@ -0,0 +1,106 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// address of a port which is free right now
+fn free_address() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn start(args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_checkers"))
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+fn output(child: Child) -> String {
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn result_line(output: &str) -> String {
+    output.lines().find(|line| line.starts_with("result: ")).unwrap().to_string()
+}
+
+fn receive(reader: &mut impl BufRead) -> String {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    line.trim().to_string()
+}
+
+#[test]
+fn full_game_between_two_instances() {
+    let address = free_address();
+    let host = start(&["host", &address, "--opponent", "minmax5", "--side", "black"]);
+    let guest = start(&["join", &address, "--opponent", "random"]);
+
+    let guest_text = output(guest);
+    let host_text = output(host);
+    assert!(guest_text.contains("playing white from move 0"));
+    assert!(guest_text.contains("white played "));
+    assert!(host_text.contains("black played "));
+    assert_ne!(result_line(&guest_text), "result: game is not ended");
+    assert_eq!(result_line(&guest_text), result_line(&host_text));
+}
+
+#[test]
+fn guest_joins_again() {
+    let address = free_address();
+    let host = start(&["host", &address, "--opponent", "random"]);
+
+    // the first guest is gone in the middle of the game
+    let mut guest = start(&["join", &address, "--opponent", "random"]);
+    let mut guest_output = BufReader::new(guest.stdout.take().unwrap());
+    while !receive(&mut guest_output).starts_with("black played ") {}
+    guest.kill().unwrap();
+    guest.wait().unwrap();
+
+    let guest_text = output(start(&["join", &address, "--opponent", "random"]));
+    let host_text = output(host);
+    assert!(host_text.contains("disconnected"));
+    let synced = guest_text.lines().next().unwrap();
+    assert!(synced.starts_with("playing black from move "));
+    assert_ne!(synced, "playing black from move 0");
+    assert_eq!(result_line(&guest_text), result_line(&host_text));
+}
+
+#[test]
+fn bad_move_draw_offer_and_resign() {
+    let address = free_address();
+    let mut host = start(&["host", &address, "--opponent", "random"]);
+    let mut host_output = BufReader::new(host.stdout.take().unwrap());
+    let mut stream = loop {
+        if let Ok(stream) = TcpStream::connect(&address) {
+            break stream;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    // the host plays white and moves right after the game is sent
+    assert_eq!(receive(&mut reader), "sync black ongoing");
+    assert!(receive(&mut reader).starts_with("move "));
+
+    // a white piece can't move on the turn of black, the host sends its game again
+    writeln!(stream, "move 22-18").unwrap();
+    assert!(receive(&mut reader).starts_with("sync black ongoing "));
+
+    writeln!(stream, "draw-offer").unwrap();
+    assert_eq!(receive(&mut reader), "draw-decline");
+
+    writeln!(stream, "resign").unwrap();
+    drop(reader);
+    drop(stream);
+    let mut host_text = String::new();
+    host_output.read_to_string(&mut host_text).unwrap();
+    assert!(host.wait().unwrap().success());
+    assert!(host_text.contains("opponent made an unavailable move: "));
+    assert!(host_text.contains("opponent resigned"));
+    assert_eq!(result_line(&host_text), "result: white won");
+}
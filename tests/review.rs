@@ -0,0 +1,28 @@
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn report_of_a_game_file() {
+    let path = std::env::temp_dir().join(format!("checkers-review-{}.pdn", std::process::id()));
+    // white gives away the man on 18, 22-17 keeps it covered by 21
+    fs::write(&path, "[FEN \"W:W21,22:B14,1\"]\n1. 22-18 14x23 *\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_checkers"))
+        .args(["review", path.to_str().unwrap(), "--depth", "4"])
+        .output()
+        .unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.contains("1. 22-18?"));
+    assert!(text.contains("better 22-17"));
+    assert!(text.contains("black: accuracy 100%"));
+}
+
+#[test]
+fn bad_arguments() {
+    let output = Command::new(env!("CARGO_BIN_EXE_checkers")).args(["review"]).output().unwrap();
+    assert!(!output.status.success());
+    let output = Command::new(env!("CARGO_BIN_EXE_checkers")).args(["review", "game.pdn", "--depth", "0"]).output().unwrap();
+    assert!(!output.status.success());
+}
@@ -0,0 +1,104 @@
+#![cfg(feature = "server")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// `checkers serve` on a free port, killed when dropped
+struct Server {
+    process: Child,
+    address: String,
+}
+
+impl Server {
+    fn start() -> Server {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_checkers"))
+            .args(["serve", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(process.stdout.as_mut().unwrap()).read_line(&mut line).unwrap();
+        let address = line.trim().strip_prefix("listening on ").unwrap().to_string();
+        Server {process, address}
+    }
+
+    /// status and body of the response
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method, path, self.address, body.len(), body,
+        ).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+        let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+        (status, body)
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+const START: &str = "W:W21,22,23,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,11,12";
+
+#[test]
+fn moves_of_start_position() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/moves", &format!("{{\"position\": \"{}\"}}", START));
+    assert_eq!(status, 200);
+    assert!(body.contains("\"state\":\"white_turn\""));
+    assert!(body.contains("\"notation\":\"22-18\""));
+    assert_eq!(body.matches("\"from\"").count(), 7);
+}
+
+#[test]
+fn apply_move() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/apply", &format!("{{\"position\": \"{}\", \"move\": \"22-18\"}}", START));
+    assert_eq!(status, 200);
+    assert_eq!(body, "{\"position\":\"B:W18,21,23,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,11,12\",\"state\":\"black_turn\"}");
+
+    let (status, body) = server.request("POST", "/apply", &format!("{{\"position\": \"{}\", \"move\": \"11-15\"}}", START));
+    assert_eq!(status, 400);
+    assert!(body.contains("\"error\""));
+
+    // the second jump of 22x15x6 is missing
+    let (status, body) = server.request("POST", "/apply", "{\"position\": \"W:W22:B18,10\", \"move\": \"22x15\"}");
+    assert_eq!(status, 400);
+    assert!(body.contains("turn is not finished"));
+}
+
+#[test]
+fn best_move_takes_pieces() {
+    let server = Server::start();
+    let (status, body) = server.request("POST", "/bestmove", "{\"position\": \"W:W22:B18,10\", \"depth\": 3}");
+    assert_eq!(status, 200);
+    assert!(body.contains("\"move\":\"22x15x6\""));
+    assert!(body.contains("\"score\":100"));
+
+    let (status, body) = server.request("POST", "/bestmove", &format!("{{\"position\": \"{}\", \"time\": 0.2}}", START));
+    assert_eq!(status, 200);
+    assert!(body.contains("\"move\":\""));
+
+    for time in ["1e300", "61"] {
+        let (status, body) = server.request("POST", "/bestmove", &format!("{{\"position\": \"{}\", \"time\": {}}}", START, time));
+        assert_eq!(status, 400);
+        assert!(body.contains("time must be at most 60 seconds"));
+    }
+}
+
+#[test]
+fn bad_requests() {
+    let server = Server::start();
+    assert_eq!(server.request("POST", "/moves", "{\"position\": \"X\"}").0, 400);
+    assert_eq!(server.request("POST", "/moves", "not json").0, 400);
+    assert_eq!(server.request("GET", "/moves", "").0, 405);
+    assert_eq!(server.request("POST", "/nothing", "{}").0, 404);
+}